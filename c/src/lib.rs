@@ -348,6 +348,12 @@ pub extern "C" fn lightningcss_stylesheet_to_css(
     } else {
       None
     },
+    keep_trailing_zero: false,
+    disallow_calc: false,
+    explicit_sign: false,
+    zero_threshold: 0.0,
+    unit_rewrite: None,
+    minify_absolute_lengths: false,
   };
 
   let res = unwrap!(wrapper.stylesheet.to_css(opts), error, ToCssResult::default());
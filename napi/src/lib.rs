@@ -760,6 +760,12 @@ fn compile<'i>(
         None
       },
       pseudo_classes: config.pseudo_classes.as_ref().map(|p| p.into()),
+      keep_trailing_zero: false,
+      disallow_calc: false,
+      explicit_sign: false,
+      zero_threshold: 0.0,
+      unit_rewrite: None,
+      minify_absolute_lengths: false,
     })?
   };
 
@@ -891,6 +897,12 @@ fn compile_bundle<
         None
       },
       pseudo_classes: config.pseudo_classes.as_ref().map(|p| p.into()),
+      keep_trailing_zero: false,
+      disallow_calc: false,
+      explicit_sign: false,
+      zero_threshold: 0.0,
+      unit_rewrite: None,
+      minify_absolute_lengths: false,
     })?
   };
 
@@ -1007,6 +1019,12 @@ fn compile_attr<'i>(
         None
       },
       pseudo_classes: None,
+      keep_trailing_zero: false,
+      disallow_calc: false,
+      explicit_sign: false,
+      zero_threshold: 0.0,
+      unit_rewrite: None,
+      minify_absolute_lengths: false,
     })?
   };
   Ok(AttrResult {
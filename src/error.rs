@@ -104,6 +104,14 @@ pub enum ParserError<'i> {
   UnexpectedToken(#[cfg_attr(any(feature = "serde", feature = "nodejs"), serde(skip))] Token<'i>),
   /// Maximum nesting depth was reached.
   MaximumNestingDepth,
+  /// A math function name was not immediately followed by a parenthesized block, e.g. `calc[1px]`.
+  MathFunctionExpectedParenthesis(CowArcStr<'i>),
+  /// A negative value was encountered where only non-negative values are allowed.
+  NegativeValue,
+  /// A math function was given the wrong number of arguments, e.g. `clamp(1px, 10px)`.
+  MathFunctionArgumentCount(CowArcStr<'i>),
+  /// A math function such as `calc()` was used where only a plain value is allowed.
+  MathFunctionNotAllowed(CowArcStr<'i>),
 }
 
 impl<'i> fmt::Display for ParserError<'i> {
@@ -132,6 +140,12 @@ impl<'i> fmt::Display for ParserError<'i> {
       ),
       UnexpectedToken(token) => write!(f, "Unexpected token {:?}", token),
       MaximumNestingDepth => write!(f, "Overflowed the maximum nesting depth"),
+      MathFunctionExpectedParenthesis(name) => {
+        write!(f, "Expected an opening parenthesis immediately after `{}`", name)
+      }
+      NegativeValue => write!(f, "Negative values are not allowed"),
+      MathFunctionArgumentCount(name) => write!(f, "Wrong number of arguments passed to `{}()`", name),
+      MathFunctionNotAllowed(name) => write!(f, "`{}()` is not allowed here", name),
     }
   }
 }
@@ -382,6 +396,8 @@ pub enum PrinterErrorKind {
   InvalidComposesSelector,
   /// The CSS modules pattern must end with `[local]` for use in CSS grid.
   InvalidCssModulesPatternInGrid,
+  /// A `calc()` expression was encountered, but is disallowed by the printer options.
+  CalcNotAllowed,
 }
 
 impl From<fmt::Error> for PrinterError {
@@ -402,6 +418,7 @@ impl fmt::Display for PrinterErrorKind {
       InvalidComposesNesting => write!(f, "The `composes` property cannot be used within nested rules"),
       InvalidComposesSelector => write!(f, "The `composes` property cannot be used with a simple class selector"),
       InvalidCssModulesPatternInGrid => write!(f, "The CSS modules `pattern` config must end with `[local]` for use in CSS grid line names."),
+      CalcNotAllowed => write!(f, "`calc()` expressions are not allowed. Resolve the value before printing it"),
     }
   }
 }
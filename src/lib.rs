@@ -93,6 +93,13 @@ mod tests {
     assert_eq!(res.code, expected);
   }
 
+  fn printer_options_test(source: &str, expected: &str, options: PrinterOptions) {
+    let mut stylesheet = StyleSheet::parse(&source, ParserOptions::default()).unwrap();
+    stylesheet.minify(MinifyOptions::default()).unwrap();
+    let res = stylesheet.to_css(options).unwrap();
+    assert_eq!(res.code, expected);
+  }
+
   fn prefix_test(source: &str, expected: &str, targets: Browsers) {
     let mut stylesheet = StyleSheet::parse(&source, ParserOptions::default()).unwrap();
     stylesheet
@@ -3341,6 +3348,29 @@ mod tests {
         },
       );
     }
+
+    // Functions embedding lengths that are not modeled are preserved as is.
+    minify_test(
+      ".foo { offset-path: ray(45deg closest-side) }",
+      ".foo{offset-path:ray(45deg closest-side)}",
+    );
+    minify_test(
+      ".foo { offset-path: ray(45deg closest-side at 10px 20px) }",
+      ".foo{offset-path:ray(45deg closest-side at 10px 20px)}",
+    );
+    minify_test(".foo { top: anchor(--a top) }", ".foo{top:anchor(--a top)}");
+    minify_test(
+      ".foo { left: calc(anchor(--a right) + 10px) }",
+      ".foo{left:calc(anchor(--a right) + 10px)}",
+    );
+    minify_test(
+      ".foo { width: anchor-size(--a width, 100px) }",
+      ".foo{width:anchor-size(--a width,100px)}",
+    );
+    minify_test(
+      ".foo { max-height: anchor-size(--a height) }",
+      ".foo{max-height:anchor-size(--a height)}",
+    );
   }
 
   #[test]
@@ -3948,6 +3978,34 @@ mod tests {
       );
     }
 
+    minify_test(".foo { width: min-content }", ".foo{width:min-content}");
+    minify_test(".foo { width: max-content }", ".foo{width:max-content}");
+    minify_test(".foo { width: fit-content }", ".foo{width:fit-content}");
+    minify_test(".foo { width: fit-content(200px) }", ".foo{width:fit-content(200px)}");
+    minify_test(
+      ".foo { max-width: fit-content(50%) }",
+      ".foo{max-width:fit-content(50%)}",
+    );
+    minify_test(".foo { width: fit-content(20%) }", ".foo{width:fit-content(20%)}");
+
+    use crate::properties::size::FitContent;
+    for (source, expected) in [
+      ("fit-content(20%)", "fit-content(20%)"),
+      ("fit-content(200px)", "fit-content(200px)"),
+      ("FIT-CONTENT(calc(100px + 10%))", "fit-content(calc(100px + 10%))"),
+    ] {
+      let value = FitContent::parse_string(source).unwrap();
+      assert_eq!(value.to_css_string(PrinterOptions::default()).unwrap(), expected);
+    }
+    assert_eq!(
+      FitContent::parse_string("fit-content(200px)").unwrap(),
+      FitContent(crate::values::length::LengthPercentage::px(200.0))
+    );
+    assert!(FitContent::parse_string("fit-content(auto)").is_err());
+    assert!(FitContent::parse_string("fit-content()").is_err());
+    minify_test(".foo { width: 50% }", ".foo{width:50%}");
+    minify_test(".foo { height: stretch }", ".foo{height:stretch}");
+
     minify_test(".foo { aspect-ratio: auto }", ".foo{aspect-ratio:auto}");
     minify_test(".foo { aspect-ratio: 2 / 3 }", ".foo{aspect-ratio:2/3}");
     minify_test(".foo { aspect-ratio: auto 2 / 3 }", ".foo{aspect-ratio:auto 2/3}");
@@ -7339,9 +7397,72 @@ mod tests {
     minify_test(".foo { width: calc(20px * 2) }", ".foo{width:40px}");
     minify_test(".foo { font-size: calc(100vw / 35) }", ".foo{font-size:2.85714vw}");
     minify_test(".foo { width: calc(20px * 2 * 3) }", ".foo{width:120px}");
+    minify_test(".foo { width: calc(2px * 3 * 4) }", ".foo{width:24px}");
+    minify_test(".foo { width: calc(2 * 3px * 4) }", ".foo{width:24px}");
+    minify_test(".foo { width: calc(2 * 3 * 4px) }", ".foo{width:24px}");
     minify_test(".foo { width: calc(20px + 30px) }", ".foo{width:50px}");
     minify_test(".foo { width: calc(20px + 30px + 40px) }", ".foo{width:90px}");
+    minify_test(".foo { width: calc(1em + 2px + 3em) }", ".foo{width:calc(4em + 2px)}");
+    minify_test(".foo { width: calc(2.5 * 4px) }", ".foo{width:10px}");
+    minify_test(".foo { width: calc(0.7 * 10px) }", ".foo{width:7px}");
+    minify_test(".foo { width: calc(100px / 7 * 7) }", ".foo{width:100px}");
     minify_test(".foo { width: calc(100% - 30px) }", ".foo{width:calc(100% - 30px)}");
+    minify_test(".foo { width: calc(50% + 10px) }", ".foo{width:calc(10px + 50%)}");
+    minify_test(
+      ".foo { width: calc((10px + 50%) * 2) }",
+      ".foo{width:calc(20px + 100%)}",
+    );
+    minify_test(
+      ".foo { width: calc(100% - (2 * (1em + 1vw))) }",
+      ".foo{width:calc(100% - 2em - 2vw)}",
+    );
+    minify_test(
+      ".foo { width: calc(100% - (1em - 1vw)) }",
+      ".foo{width:calc(100% - 1em + 1vw)}",
+    );
+    minify_test(
+      ".foo { width: max(1em, calc(2vw * 3 - 1px)) }",
+      ".foo{width:max(1em,6vw - 1px)}",
+    );
+    minify_test(
+      ".foo { width: calc(10vw - 5em - 3vh) }",
+      ".foo{width:calc(10vw - 5em - 3vh)}",
+    );
+    minify_test(
+      ".foo { width: calc(-10vw - 5em - 3rem) }",
+      ".foo{width:calc(-10vw - 5em - 3rem)}",
+    );
+    minify_test(".foo { width: calc(10px - 5px - 3px) }", ".foo{width:2px}");
+    minify_test(
+      ".foo { width: calc(-5em - 2vw + 10px) }",
+      ".foo{width:calc(10px - 5em - 2vw)}",
+    );
+    minify_test(".foo { width: calc(-5em + 10vw) }", ".foo{width:calc(10vw - 5em)}");
+    minify_test(".foo { width: calc(-5em - 10vw) }", ".foo{width:calc(-5em - 10vw)}");
+    minify_test(
+      ".foo { width: calc(-5em + max(1px, 1vw)) }",
+      ".foo{width:calc(-5em + max(1px,1vw))}",
+    );
+    // Tree-counting functions are not modeled, but are preserved.
+    minify_test(
+      ".foo { width: calc(10px * sibling-index()) }",
+      ".foo{width:calc(10px*sibling-index())}",
+    );
+    minify_test(
+      ".foo { margin-left: calc(sibling-count() * 1em + 2px) }",
+      ".foo{margin-left:calc(sibling-count()*1em + 2px)}",
+    );
+    minify_test(".foo { width: calc(0px - 5px) }", ".foo{width:-5px}");
+    minify_test(".foo { width: calc(0em - 5px) }", ".foo{width:-5px}");
+    minify_test(".foo { width: calc(0px - 5em) }", ".foo{width:-5em}");
+    minify_test(".foo { width: calc(0px - 5px - 1em) }", ".foo{width:calc(-5px - 1em)}");
+    minify_test(".foo { margin-left: -50% }", ".foo{margin-left:-50%}");
+    minify_test(".foo { translate: -50% -50% }", ".foo{translate:-50% -50%}");
+    minify_test(".foo { width: calc(0em + 50%) }", ".foo{width:50%}");
+    minify_test(".foo { width: max(0em, 50%) }", ".foo{width:max(0em,50%)}");
+    minify_test(".foo { width: 0em }", ".foo{width:0}");
+    minify_test(".foo { width: calc(10px + 50%) }", ".foo{width:calc(10px + 50%)}");
+    minify_test(".foo { width: calc(-10px + 50%) }", ".foo{width:calc(50% - 10px)}");
     minify_test(
       ".foo { width: calc(100% - 30px + 20px) }",
       ".foo{width:calc(100% - 10px)}",
@@ -7377,23 +7498,23 @@ mod tests {
     );
     minify_test(
       ".foo { width: calc(1px - (2em + 3%)) }",
-      ".foo{width:calc(1px + -2em - 3%)}",
-    ); // TODO: fix sign
+      ".foo{width:calc(1px - 2em - 3%)}",
+    );
     minify_test(
       ".foo { width: calc((100vw - 50em) / 2) }",
       ".foo{width:calc(50vw - 25em)}",
     );
     minify_test(
       ".foo { width: calc(1px - (2em + 4vh + 3%)) }",
-      ".foo{width:calc(1px + -2em - 4vh - 3%)}",
-    ); // TODO
+      ".foo{width:calc(1px - 2em - 4vh - 3%)}",
+    );
     minify_test(
       ".foo { width: calc(1px + (2em + (3vh + 4px))) }",
       ".foo{width:calc(2em + 3vh + 5px)}",
     );
     minify_test(
       ".foo { width: calc(1px - (2em + 4px - 6vh) / 2) }",
-      ".foo{width:calc(-1em - 1px + 3vh)}",
+      ".foo{width:calc(3vh - 1em - 1px)}",
     );
     minify_test(
       ".foo { width: calc(100% - calc(50% + 25px)) }",
@@ -7415,11 +7536,11 @@ mod tests {
     );
     minify_test(
       ".foo { width: calc(100% + (2 * 100px) - ((75.37% - 63.5px) - 900px)) }",
-      ".foo{width:calc(24.63% + 1163.5px)}",
+      ".foo{width:calc(1163.5px + 24.63%)}",
     );
     minify_test(
       ".foo { width: calc(((((100% + (2 * 30px) + 63.5px) / 0.7537) - (100vw - 60px)) / 2) + 30px) }",
-      ".foo{width:calc(66.3394% + 141.929px - 50vw)}",
+      ".foo{width:calc(141.929px - 50vw + 66.3394%)}",
     );
     minify_test(
       ".foo { width: calc(((75.37% - 63.5px) - 900px) + (2 * 100px)) }",
@@ -7660,14 +7781,18 @@ mod tests {
   fn test_trig() {
     minify_test(".foo { width: calc(2px * pi); }", ".foo{width:6.28319px}");
     minify_test(".foo { width: calc(2px / pi); }", ".foo{width:.63662px}");
-    // minify_test(
-    //   ".foo { width: calc(2px * infinity); }",
-    //   ".foo{width:calc(2px*infinity)}",
-    // );
-    // minify_test(
-    //   ".foo { width: calc(2px * -infinity); }",
-    //   ".foo{width:calc(2px*-infinity)}",
-    // );
+    minify_test(
+      ".foo { width: calc(2px * infinity); }",
+      ".foo{width:calc(infinity*1px)}",
+    );
+    minify_test(
+      ".foo { width: calc(2px * -infinity); }",
+      ".foo{width:calc(-infinity*1px)}",
+    );
+    minify_test(
+      ".foo { border-radius: calc(infinity * 1px); }",
+      ".foo{border-radius:calc(infinity*1px)}",
+    );
     minify_test(".foo { width: calc(100px * sin(45deg))", ".foo{width:70.7107px}");
     minify_test(".foo { width: calc(100px * sin(.125turn))", ".foo{width:70.7107px}");
     minify_test(
@@ -11645,6 +11770,13 @@ mod tests {
     minify_test(".foo { translate: 1px 0px 0px }", ".foo{translate:1px}");
     minify_test(".foo { translate: 1px 2px 0px }", ".foo{translate:1px 2px}");
     minify_test(".foo { translate: 1px 0px 2px }", ".foo{translate:1px 0 2px}");
+    minify_test(".foo { translate: 10px }", ".foo{translate:10px}");
+    minify_test(".foo { translate: 10px 20px }", ".foo{translate:10px 20px}");
+    minify_test(".foo { translate: 10px 20px 5px }", ".foo{translate:10px 20px 5px}");
+    minify_test(
+      ".foo { translate: 50% calc(10px + 1em) }",
+      ".foo{translate:50% calc(10px + 1em)}",
+    );
     minify_test(".foo { translate: none }", ".foo{translate:0}");
     minify_test(".foo { rotate: 10deg }", ".foo{rotate:10deg}");
     minify_test(".foo { rotate: z 10deg }", ".foo{rotate:10deg}");
@@ -14986,6 +15118,10 @@ mod tests {
 
   #[test]
   fn test_position() {
+    minify_test(".foo { inset-inline-start: AUTO }", ".foo{inset-inline-start:auto}");
+    minify_test(".foo { inset-block: Auto 10px }", ".foo{inset-block:auto 10px}");
+    minify_test(".foo { left: AuTo }", ".foo{left:auto}");
+
     test(
       r#"
       .foo {
@@ -20182,6 +20318,14 @@ mod tests {
       ".foo { grid-template-columns: minmax(min-content, 1fr); }",
       ".foo{grid-template-columns:minmax(min-content,1fr)}",
     );
+    minify_test(
+      ".foo { grid-template-columns: minmax(100px, 1fr); }",
+      ".foo{grid-template-columns:minmax(100px,1fr)}",
+    );
+    minify_test(
+      ".foo { grid-auto-rows: minmax(calc(10px + 1em), 50%); }",
+      ".foo{grid-auto-rows:minmax(calc(10px + 1em),50%)}",
+    );
     minify_test(
       ".foo { grid-template-columns: 200px repeat(auto-fill, 100px) 300px; }",
       ".foo{grid-template-columns:200px repeat(auto-fill,100px) 300px}",
@@ -20928,6 +21072,9 @@ mod tests {
     minify_test(".foo { --test:foo; }", ".foo{--test:foo}");
     minify_test(".foo { --test:foo ; }", ".foo{--test:foo}");
     minify_test(".foo { --test: var(--foo, 20px); }", ".foo{--test:var(--foo,20px)}");
+    minify_test(".foo { width: var(--w, 10px); }", ".foo{width:var(--w,10px)}");
+    // Fallbacks are only validated when they are used, so invalid ones are preserved.
+    minify_test(".foo { width: var(--w, 10foo); }", ".foo{width:var(--w,10foo)}");
     minify_test(
       ".foo { transition: var(--foo, 20px),\nvar(--bar, 40px); }",
       ".foo{transition:var(--foo,20px),var(--bar,40px)}",
@@ -26889,6 +27036,177 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_keep_trailing_zero() {
+    let options = || PrinterOptions {
+      minify: true,
+      keep_trailing_zero: true,
+      ..PrinterOptions::default()
+    };
+    printer_options_test(".foo { width: 1.0px }", ".foo{width:1.0px}", options());
+    printer_options_test(".foo { width: -2px }", ".foo{width:-2.0px}", options());
+    printer_options_test(".foo { width: 1.50px }", ".foo{width:1.5px}", options());
+    printer_options_test(".foo { width: 10px }", ".foo{width:10.0px}", options());
+    printer_options_test(".foo { width: .5em }", ".foo{width:.5em}", options());
+    printer_options_test(".foo { width: 0.0px }", ".foo{width:0}", options());
+    printer_options_test(".foo { margin: 1px 2px }", ".foo{margin:1.0px 2.0px}", options());
+    printer_options_test(".foo { --x: 1px }", ".foo{--x:1px}", options());
+    printer_options_test(
+      ".foo { width: 1.0px }",
+      ".foo{width:1px}",
+      PrinterOptions {
+        minify: true,
+        ..PrinterOptions::default()
+      },
+    );
+    // Only lengths are printed with a trailing zero.
+    printer_options_test(
+      ".foo { grid-template-columns: 1.0fr 2fr }",
+      ".foo{grid-template-columns:1fr 2fr}",
+      options(),
+    );
+    printer_options_test(".foo { rotate: 45.0deg }", ".foo{rotate:45deg}", options());
+  }
+
+  #[test]
+  fn test_explicit_sign() {
+    let options = || PrinterOptions {
+      minify: true,
+      explicit_sign: true,
+      ..PrinterOptions::default()
+    };
+    printer_options_test(".foo { width: 10px }", ".foo{width:+10px}", options());
+    printer_options_test(".foo { width: -10px }", ".foo{width:-10px}", options());
+    printer_options_test(".foo { width: .5em }", ".foo{width:+.5em}", options());
+    printer_options_test(".foo { width: 0px }", ".foo{width:0}", options());
+    printer_options_test(
+      ".foo { width: calc(100% - 10px) }",
+      ".foo{width:calc(100% - 10px)}",
+      options(),
+    );
+    printer_options_test(
+      ".foo { width: calc(1em + 2px) }",
+      ".foo{width:calc(1em + 2px)}",
+      options(),
+    );
+    printer_options_test(".foo { --x: 10px }", ".foo{--x:10px}", options());
+    // Only lengths are signed.
+    printer_options_test(
+      ".foo { grid-template-columns: 1fr 2fr }",
+      ".foo{grid-template-columns:1fr 2fr}",
+      options(),
+    );
+    printer_options_test(".foo { rotate: 45deg }", ".foo{rotate:45deg}", options());
+    printer_options_test(
+      "@media (min-resolution: 2dppx) { .foo { color: red } }",
+      "@media (resolution>=2x){.foo{color:red}}",
+      options(),
+    );
+  }
+
+  #[test]
+  fn test_zero_threshold() {
+    let options = |zero_threshold| PrinterOptions {
+      minify: true,
+      zero_threshold,
+      ..PrinterOptions::default()
+    };
+    printer_options_test(".foo { width: 0.005px }", ".foo{width:.005px}", options(0.0));
+    printer_options_test(".foo { width: 0.005px }", ".foo{width:0}", options(0.01));
+    printer_options_test(".foo { width: -0.005px }", ".foo{width:0}", options(0.01));
+    printer_options_test(".foo { width: 0.01px }", ".foo{width:.01px}", options(0.01));
+    printer_options_test(".foo { width: 0.0001in }", ".foo{width:0}", options(0.01));
+    printer_options_test(".foo { width: 0.005em }", ".foo{width:.005em}", options(0.01));
+    printer_options_test(
+      ".foo { width: calc(0.005px + 1em) }",
+      ".foo{width:calc(.005px + 1em)}",
+      options(0.01),
+    );
+  }
+
+  #[test]
+  fn test_unit_rewrite() {
+    let options = || PrinterOptions {
+      minify: true,
+      unit_rewrite: Some(|unit| unit.to_ascii_uppercase().into()),
+      ..PrinterOptions::default()
+    };
+    printer_options_test(".foo { width: 10px }", ".foo{width:10PX}", options());
+    printer_options_test(".foo { width: .5em }", ".foo{width:.5EM}", options());
+    printer_options_test(".foo { width: 0px }", ".foo{width:0}", options());
+    printer_options_test(
+      ".foo { width: calc(1em + 2vw) }",
+      ".foo{width:calc(1EM + 2VW)}",
+      options(),
+    );
+    printer_options_test(".foo { --x: 10px }", ".foo{--x:10px}", options());
+  }
+
+  #[test]
+  fn test_disallow_calc() {
+    fn calc_error_test(source: &str) {
+      let mut stylesheet = StyleSheet::parse(&source, ParserOptions::default()).unwrap();
+      stylesheet.minify(MinifyOptions::default()).unwrap();
+      let res = stylesheet.to_css(PrinterOptions {
+        disallow_calc: true,
+        ..PrinterOptions::default()
+      });
+      match res {
+        Err(e) => assert_eq!(e.kind, PrinterErrorKind::CalcNotAllowed),
+        _ => unreachable!(),
+      }
+    }
+
+    calc_error_test(".foo { width: calc(1em + 2px) }");
+    calc_error_test(".foo { width: min(1em, 2px) }");
+    let options = || PrinterOptions {
+      minify: true,
+      disallow_calc: true,
+      ..PrinterOptions::default()
+    };
+    printer_options_test(".foo { width: calc(1px + 2px) }", ".foo{width:3px}", options());
+    printer_options_test(".foo { width: 10px }", ".foo{width:10px}", options());
+  }
+
+  #[test]
+  fn test_minify_absolute_lengths() {
+    let options = || PrinterOptions {
+      minify: true,
+      minify_absolute_lengths: true,
+      ..PrinterOptions::default()
+    };
+    printer_options_test(
+      ".foo { width: 96px; height: 12pt; margin: 19.2px }",
+      ".foo{width:1in;height:1pc;margin:.2in}",
+      options(),
+    );
+    printer_options_test(
+      ".foo { width: 72px; height: 1cm; margin: 16em }",
+      ".foo{width:72px;height:1cm;margin:16em}",
+      options(),
+    );
+    printer_options_test(
+      ".foo { width: calc(100% - 96px) }",
+      ".foo{width:calc(100% - 1in)}",
+      options(),
+    );
+    printer_options_test(".foo { --x: 96px }", ".foo{--x:96px}", options());
+    printer_options_test(".foo { width: 1e30px }", ".foo{width:1e30px}", options());
+    printer_options_test(
+      ".foo { width: calc(infinity * 1px); height: calc(NaN * 1pt) }",
+      ".foo{width:calc(infinity*1px);height:calc(NaN*1pt)}",
+      options(),
+    );
+    printer_options_test(
+      ".foo { width: 96px }",
+      ".foo {\n  width: 96px;\n}\n",
+      PrinterOptions {
+        minify_absolute_lengths: true,
+        ..PrinterOptions::default()
+      },
+    );
+  }
+
   #[test]
   fn test_environment() {
     minify_test(
@@ -26935,6 +27253,17 @@ mod tests {
       "@media (width<=env(safe-area-inset-top)){body{padding:env(safe-area-inset-top)}}",
     );
 
+    // Lengths computed from environment variables are preserved until computed-value time.
+    minify_test(
+      ".foo { padding-top: env(safe-area-inset-top, 20px) }",
+      ".foo{padding-top:env(safe-area-inset-top,20px)}",
+    );
+    minify_test(
+      ".foo { width: calc(env(safe-area-inset-left, 0px) + 10px) }",
+      ".foo{width:calc(env(safe-area-inset-left,0px) + 10px)}",
+    );
+    minify_test(".foo { width: toggle(1px, 2em) }", ".foo{width:toggle(1px,2em)}");
+
     minify_test(
       r#"
       @media (max-width: env(unknown)) {
@@ -10,6 +10,7 @@ use crate::vendor_prefix::VendorPrefix;
 use cssparser::{serialize_identifier, serialize_name};
 #[cfg(feature = "sourcemap")]
 use parcel_sourcemap::{OriginalLocation, SourceMap};
+use std::borrow::Cow;
 
 /// Options that control how CSS is serialized to a string.
 #[derive(Default)]
@@ -35,6 +36,25 @@ pub struct PrinterOptions<'a> {
   /// A mapping of pseudo classes to replace with class names that can be applied
   /// from JavaScript. Useful for polyfills, for example.
   pub pseudo_classes: Option<PseudoClasses<'a>>,
+  /// Whether to print a trailing `.0` on integral lengths, e.g. `1.0px` rather than `1px`.
+  /// Other dimensions, such as angles and flex values, are unaffected.
+  pub keep_trailing_zero: bool,
+  /// Whether to return an error when a `calc()` expression would be printed, rather than
+  /// printing it. Useful for targets that do not support `calc()` at all.
+  pub disallow_calc: bool,
+  /// Whether to print a `+` sign before positive lengths, e.g. `+10px`. Other dimensions,
+  /// such as angles and flex values, are unaffected. Signs are never added within `calc()` expressions.
+  pub explicit_sign: bool,
+  /// Absolute lengths smaller than this many pixels are printed as `0`, e.g. `0.005px` with
+  /// a threshold of `0.01`. Relative lengths and `calc()` expressions are unaffected.
+  /// Defaults to `0`, which disables this.
+  pub zero_threshold: f32,
+  /// A function to transform the unit of each length before it is printed, e.g. to
+  /// uppercase it. Lengths within custom properties are not affected.
+  pub unit_rewrite: Option<fn(&str) -> Cow<'_, str>>,
+  /// Whether to print absolute lengths in the equivalent unit with the shortest representation
+  /// when minifying, e.g. `96px` as `1in`. Only lossless conversions are performed.
+  pub minify_absolute_lengths: bool,
 }
 
 /// A mapping of user action pseudo classes to replace with class names.
@@ -82,6 +102,17 @@ pub struct Printer<'a, 'b, 'c, W> {
   /// the vendor prefix of whatever is being printed.
   pub(crate) vendor_prefix: VendorPrefix,
   pub(crate) in_calc: bool,
+  /// Whether a custom property value is being printed. Its tokens are written as authored.
+  pub(crate) in_custom_property: bool,
+  /// A reusable buffer for values that must be serialized and post-processed
+  /// before being written to the destination.
+  pub(crate) scratch: String,
+  pub(crate) keep_trailing_zero: bool,
+  pub(crate) disallow_calc: bool,
+  pub(crate) explicit_sign: bool,
+  pub(crate) zero_threshold: f32,
+  pub(crate) unit_rewrite: Option<fn(&str) -> Cow<'_, str>>,
+  pub(crate) minify_absolute_lengths: bool,
   pub(crate) css_module: Option<CssModule<'a, 'b, 'c>>,
   pub(crate) dependencies: Option<Vec<Dependency>>,
   pub(crate) remove_imports: bool,
@@ -111,6 +142,14 @@ impl<'a, 'b, 'c, W: std::fmt::Write + Sized> Printer<'a, 'b, 'c, W> {
       targets: options.targets,
       vendor_prefix: VendorPrefix::empty(),
       in_calc: false,
+      in_custom_property: false,
+      scratch: String::new(),
+      keep_trailing_zero: options.keep_trailing_zero,
+      disallow_calc: options.disallow_calc,
+      explicit_sign: options.explicit_sign,
+      zero_threshold: options.zero_threshold,
+      unit_rewrite: options.unit_rewrite,
+      minify_absolute_lengths: options.minify_absolute_lengths,
       css_module: None,
       dependencies: if options.analyze_dependencies.is_some() {
         Some(Vec::new())
@@ -334,6 +373,18 @@ impl<'a, 'b, 'c, W: std::fmt::Write + Sized> Printer<'a, 'b, 'c, W> {
     }
   }
 
+  /// Returns an error of the given kind at the current location in the source file being printed.
+  pub(crate) fn error_at_current_loc(&self, kind: PrinterErrorKind) -> Error<PrinterErrorKind> {
+    Error {
+      kind,
+      loc: Some(ErrorLocation {
+        filename: self.filename().into(),
+        line: self.loc.line,
+        column: self.loc.column,
+      }),
+    }
+  }
+
   pub(crate) fn with_context<T, U, F: FnOnce(&mut Printer<'a, 'b, 'c, W>) -> Result<T, U>>(
     &mut self,
     selectors: &SelectorList,
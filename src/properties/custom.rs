@@ -1,9 +1,9 @@
 //! CSS custom properties and unparsed token values.
 
-use crate::error::{ParserError, PrinterError, PrinterErrorKind};
+use crate::error::{Error, ParserError, PrinterError, PrinterErrorKind};
 use crate::macros::enum_property;
 use crate::prefixes::Feature;
-use crate::printer::Printer;
+use crate::printer::{Printer, PrinterOptions};
 use crate::properties::PropertyId;
 use crate::rules::supports::SupportsCondition;
 use crate::stylesheet::ParserOptions;
@@ -15,7 +15,7 @@ use crate::values::color::{
   HSL, RGBA, SRGB,
 };
 use crate::values::ident::{CustomIdent, DashedIdent, DashedIdentReference, Ident};
-use crate::values::length::{serialize_dimension, LengthValue};
+use crate::values::length::{serialize_dimension, serialize_length, Length, LengthValue};
 use crate::values::number::CSSInteger;
 use crate::values::percentage::Percentage;
 use crate::values::resolution::Resolution;
@@ -180,7 +180,6 @@ impl<'i> UnparsedProperty<'i> {
     vars: &std::collections::HashMap<&str, TokenList<'i>>,
   ) -> Result<super::Property<'x>, ()> {
     use super::Property;
-    use crate::stylesheet::PrinterOptions;
     use static_self::IntoOwned;
 
     // Substitute variables in the token list.
@@ -533,6 +532,17 @@ impl<'i> TokenList<'i> {
       return Ok(());
     }
 
+    let was_in_custom_property = dest.in_custom_property;
+    dest.in_custom_property |= is_custom_property;
+    let res = self.write_tokens(dest, is_custom_property);
+    dest.in_custom_property = was_in_custom_property;
+    res
+  }
+
+  fn write_tokens<W>(&self, dest: &mut Printer<W>, is_custom_property: bool) -> Result<(), PrinterError>
+  where
+    W: std::fmt::Write,
+  {
     let mut has_whitespace = false;
     for (i, token_or_value) in self.0.iter().enumerate() {
       has_whitespace = match token_or_value {
@@ -571,7 +581,7 @@ impl<'i> TokenList<'i> {
         TokenOrValue::Length(v) => {
           // Do not serialize unitless zero lengths in custom properties as it may break calc().
           let (value, unit) = v.to_unit_value();
-          serialize_dimension(value, unit, dest)?;
+          serialize_length(value, unit, dest)?;
           false
         }
         TokenOrValue::Angle(v) => {
@@ -1264,6 +1274,39 @@ impl<'i> Variable<'i> {
     dest.write_char(')')
   }
 
+  /// Parses the fallback value as a length, without resolving the variable.
+  /// Returns `None` if there is no fallback, or an error if the fallback is not a valid length.
+  ///
+  /// Fallbacks are not validated when they are parsed, because they are only invalid if they are
+  /// actually used, so this allows individual properties to check them when needed. Errors have
+  /// no location, because the source location of the fallback is not recorded.
+  pub fn fallback_length(&self) -> Option<Result<Length, Error<ParserError<'i>>>> {
+    let fallback = self.fallback.as_ref()?;
+    let mut tokens = fallback
+      .0
+      .iter()
+      .filter(|token| !matches!(token, TokenOrValue::Token(Token::WhiteSpace(..))));
+
+    // Plain lengths are already parsed within the token list.
+    if let (Some(TokenOrValue::Length(length)), None) = (tokens.next(), tokens.next()) {
+      return Some(Ok(Length::Value(length.clone())));
+    }
+
+    // Otherwise, e.g. for calc(), print and re-parse the tokens, as cssparser doesn't provide a way
+    // to parse from existing tokens. Errors cannot borrow from the printed string, so they are
+    // reported without the unexpected token.
+    let mut css = String::new();
+    let mut dest = Printer::new(&mut css, PrinterOptions::default());
+    fallback.to_css(&mut dest, false).unwrap();
+    Some(Length::parse_string(css.trim()).map_err(|err| Error {
+      kind: match err.kind {
+        ParseErrorKind::Basic(BasicParseErrorKind::EndOfInput) => ParserError::EndOfInput,
+        _ => ParserError::InvalidValue,
+      },
+      loc: None,
+    }))
+  }
+
   fn get_fallback(&self, kind: ColorFallbackKind) -> Self {
     Variable {
       name: self.name.clone(),
@@ -1667,3 +1710,62 @@ impl<'i> UnresolvedColor<'i> {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse_variable(source: &str) -> Variable {
+    let mut input = ParserInput::new(source);
+    let mut parser = Parser::new(&mut input);
+    parser.expect_function_matching("var").unwrap();
+    parser
+      .parse_nested_block(|input| Variable::parse(input, &ParserOptions::default(), 0))
+      .unwrap()
+  }
+
+  #[test]
+  fn test_fallback_length() {
+    let var = parse_variable("var(--w, 10px)");
+    assert_eq!(var.fallback_length(), Some(Ok(Length::px(10.0))));
+    let mut s = String::new();
+    var.to_css(&mut Printer::new(&mut s, PrinterOptions::default()), false).unwrap();
+    assert_eq!(s, "var(--w, 10px)");
+
+    assert_eq!(
+      parse_variable("var(--w, calc(1em + 2px))").fallback_length(),
+      Some(Ok(Length::parse_string("calc(1em + 2px)").unwrap()))
+    );
+    let invalid = |source| parse_variable(source).fallback_length().map(|res| res.map_err(|err| err.kind));
+    assert_eq!(invalid("var(--w, 10foo)"), Some(Err(ParserError::InvalidValue)));
+    assert_eq!(invalid("var(--w, 10px 20px)"), Some(Err(ParserError::InvalidValue)));
+    assert_eq!(
+      invalid("var(--w, calc(1em + red))"),
+      Some(Err(ParserError::InvalidValue))
+    );
+    assert!(matches!(
+      parse_variable("var(--w, 10foo)").fallback_length(),
+      Some(Err(Error { loc: None, .. }))
+    ));
+    assert_eq!(parse_variable("var(--w)").fallback_length(), None);
+  }
+
+  #[test]
+  fn test_in_custom_property() {
+    use crate::stylesheet::StyleSheet;
+    let stylesheet = StyleSheet::parse(
+      ".foo { --x: 96px 0px 2px; --y: calc(var(--x) + 0px); width: 2px; }",
+      ParserOptions::default(),
+    )
+    .unwrap();
+    let res = stylesheet
+      .to_css(PrinterOptions {
+        minify: true,
+        keep_trailing_zero: true,
+        explicit_sign: true,
+        ..PrinterOptions::default()
+      })
+      .unwrap();
+    assert_eq!(res.code, ".foo{--x:96px 0px 2px;--y:calc(var(--x) + 0px);width:+2.0px}");
+  }
+}
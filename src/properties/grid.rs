@@ -7,6 +7,7 @@ use crate::declaration::{DeclarationBlock, DeclarationList};
 use crate::error::{Error, ErrorLocation, ParserError, PrinterError, PrinterErrorKind};
 use crate::macros::{define_shorthand, impl_shorthand};
 use crate::printer::Printer;
+use crate::properties::size::FitContent;
 use crate::properties::{Property, PropertyId};
 use crate::traits::{Parse, PropertyHandler, Shorthand, ToCss};
 use crate::values::ident::CustomIdent;
@@ -213,8 +214,7 @@ impl<'i> Parse<'i> for TrackSize {
       });
     }
 
-    input.expect_function_matching("fit-content")?;
-    let len = input.parse_nested_block(LengthPercentage::parse)?;
+    let FitContent(len) = FitContent::parse(input)?;
     Ok(TrackSize::FitContent(len))
   }
 }
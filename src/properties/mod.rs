@@ -119,6 +119,7 @@ pub mod text;
 pub mod transform;
 pub mod transition;
 pub mod ui;
+pub mod writing_modes;
 
 use crate::declaration::DeclarationBlock;
 use crate::error::{ParserError, PrinterError};
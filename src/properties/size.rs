@@ -85,7 +85,7 @@ impl<'i> Parse<'i> for Size {
       return res;
     }
 
-    if let Ok(res) = input.try_parse(parse_fit_content) {
+    if let Ok(FitContent(res)) = input.try_parse(FitContent::parse) {
       return Ok(Size::FitContentFunction(res));
     }
 
@@ -219,7 +219,7 @@ impl<'i> Parse<'i> for MaxSize {
       return res;
     }
 
-    if let Ok(res) = input.try_parse(parse_fit_content) {
+    if let Ok(FitContent(res)) = input.try_parse(FitContent::parse) {
       return Ok(MaxSize::FitContentFunction(res));
     }
 
@@ -289,11 +289,31 @@ impl IsCompatible for MaxSize {
   }
 }
 
-fn parse_fit_content<'i, 't>(
-  input: &mut Parser<'i, 't>,
-) -> Result<LengthPercentage, ParseError<'i, ParserError<'i>>> {
-  input.expect_function_matching("fit-content")?;
-  input.parse_nested_block(|input| LengthPercentage::parse(input))
+/// The [fit-content()](https://drafts.csswg.org/css-sizing-4/#sizing-values) function,
+/// used by sizing properties and grid track sizes.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "visitor", derive(Visit))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "into_owned", derive(static_self::IntoOwned))]
+pub struct FitContent(pub LengthPercentage);
+
+impl<'i> Parse<'i> for FitContent {
+  fn parse<'t>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i, ParserError<'i>>> {
+    input.expect_function_matching("fit-content")?;
+    input.parse_nested_block(|input| LengthPercentage::parse(input).map(FitContent))
+  }
+}
+
+impl ToCss for FitContent {
+  fn to_css<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
+  where
+    W: std::fmt::Write,
+  {
+    dest.write_str("fit-content(")?;
+    self.0.to_css(dest)?;
+    dest.write_char(')')
+  }
 }
 
 enum_property! {
@@ -0,0 +1,33 @@
+//! CSS properties related to writing modes.
+
+use crate::error::{ParserError, PrinterError};
+use crate::macros::enum_property;
+use crate::printer::Printer;
+use crate::traits::{Parse, ToCss};
+#[cfg(feature = "visitor")]
+use crate::visitor::Visit;
+use cssparser::*;
+
+enum_property! {
+  /// A value for the [writing-mode](https://www.w3.org/TR/css-writing-modes-4/#block-flow) property,
+  /// which determines the physical direction of the block and inline axes.
+  pub enum WritingMode {
+    /// Lines are horizontal, and the block axis runs from top to bottom.
+    "horizontal-tb": HorizontalTb,
+    /// Lines are vertical, and the block axis runs from right to left.
+    "vertical-rl": VerticalRl,
+    /// Lines are vertical, and the block axis runs from left to right.
+    "vertical-lr": VerticalLr,
+    /// Like `vertical-rl`, but all text is set sideways.
+    "sideways-rl": SidewaysRl,
+    /// Like `vertical-lr`, but all text is set sideways.
+    "sideways-lr": SidewaysLr,
+  }
+}
+
+impl WritingMode {
+  /// Returns whether the inline axis is vertical.
+  pub fn is_vertical(&self) -> bool {
+    !matches!(self, WritingMode::HorizontalTb)
+  }
+}
@@ -1,12 +1,12 @@
 //! Mathematical calculation functions and expressions.
 
 use crate::compat::Feature;
-use crate::error::{ParserError, PrinterError};
+use crate::error::{ParserError, PrinterError, PrinterErrorKind};
 use crate::macros::enum_property;
 use crate::printer::Printer;
 use crate::targets::{should_compile, Browsers};
-use crate::traits::private::AddInternal;
-use crate::traits::{IsCompatible, Parse, Sign, ToCss, TryMap, TryOp, TrySign};
+use crate::traits::private::{AddInternal, TryAdd};
+use crate::traits::{IsCompatible, Parse, Sign, ToCss, TryMap, TryOp, TrySign, Zero};
 #[cfg(feature = "visitor")]
 use crate::visitor::Visit;
 use cssparser::*;
@@ -122,6 +122,15 @@ fn modulo(a: f32, b: f32) -> f32 {
   ((a % b) + b) % b
 }
 
+/// Returns whether the given function name is a math function supported by [Calc](Calc).
+pub(crate) fn is_math_function(name: &str) -> bool {
+  match_ignore_ascii_case! { name,
+    "calc" | "min" | "max" | "clamp" | "round" | "rem" | "mod" | "sin" | "cos" | "tan" | "asin" | "acos"
+      | "atan" | "atan2" | "pow" | "log" | "sqrt" | "exp" | "hypot" | "abs" | "sign" => true,
+    _ => false
+  }
+}
+
 impl<V: ToCss + std::ops::Mul<f32, Output = V> + TrySign + Clone + std::fmt::Debug> ToCss for MathFunction<V> {
   fn to_css<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
   where
@@ -346,7 +355,18 @@ impl<
     parse_ident: Parse,
   ) -> Result<Self, ParseError<'i, ParserError<'i>>> {
     let location = input.current_source_location();
-    let f = input.expect_function()?;
+    let f = match input.next()? {
+      Token::Function(f) => f.clone(),
+      // Catch common mistakes such as `calc[...]` or `calc (...)` with a more descriptive error.
+      Token::Ident(name) if name.eq_ignore_ascii_case("calc") => {
+        let name = name.clone();
+        return Err(location.new_custom_error(ParserError::MathFunctionExpectedParenthesis(name.into())));
+      }
+      t => {
+        let t = t.clone();
+        return Err(location.new_unexpected_token_error(t));
+      }
+    };
     match_ignore_ascii_case! { &f,
       "calc" => {
         let calc = input.parse_nested_block(|input| Calc::parse_sum(input, parse_ident))?;
@@ -373,11 +393,13 @@ impl<
       },
       "clamp" => {
         let (mut min, mut center, mut max) = input.parse_nested_block(|input| {
-          let min = Some(Calc::parse_sum(input, parse_ident)?);
-          input.expect_comma()?;
-          let center: Calc<V> = Calc::parse_sum(input, parse_ident)?;
-          input.expect_comma()?;
-          let max = Some(Calc::parse_sum(input, parse_ident)?);
+          let mut args = input.parse_comma_separated(|input| Calc::parse_sum(input, parse_ident))?;
+          if args.len() != 3 {
+            return Err(location.new_custom_error(ParserError::MathFunctionArgumentCount(f.clone().into())));
+          }
+          let max = args.pop();
+          let center: Calc<V> = args.pop().unwrap();
+          let min = args.pop();
           Ok((min, center, max))
         })?;
 
@@ -874,6 +896,232 @@ impl<
   }
 }
 
+impl<V> Calc<V> {
+  /// Maps each value within the expression to a new value, preserving the structure of the expression.
+  pub(crate) fn map_values<U, F: Copy + Fn(&V) -> U>(&self, f: F) -> Calc<U> {
+    let map_vec = |args: &Vec<Calc<V>>| args.iter().map(|arg| arg.map_values(f)).collect();
+    match self {
+      Calc::Value(v) => Calc::Value(Box::new(f(v))),
+      Calc::Number(n) => Calc::Number(*n),
+      Calc::Sum(a, b) => Calc::Sum(Box::new(a.map_values(f)), Box::new(b.map_values(f))),
+      Calc::Product(num, calc) => Calc::Product(*num, Box::new(calc.map_values(f))),
+      Calc::Function(function) => Calc::Function(Box::new(match &**function {
+        MathFunction::Calc(c) => MathFunction::Calc(c.map_values(f)),
+        MathFunction::Min(args) => MathFunction::Min(map_vec(args)),
+        MathFunction::Max(args) => MathFunction::Max(map_vec(args)),
+        MathFunction::Clamp(a, b, c) => MathFunction::Clamp(a.map_values(f), b.map_values(f), c.map_values(f)),
+        MathFunction::Round(strategy, a, b) => MathFunction::Round(*strategy, a.map_values(f), b.map_values(f)),
+        MathFunction::Rem(a, b) => MathFunction::Rem(a.map_values(f), b.map_values(f)),
+        MathFunction::Mod(a, b) => MathFunction::Mod(a.map_values(f), b.map_values(f)),
+        MathFunction::Abs(v) => MathFunction::Abs(v.map_values(f)),
+        MathFunction::Sign(v) => MathFunction::Sign(v.map_values(f)),
+        MathFunction::Hypot(args) => MathFunction::Hypot(map_vec(args)),
+      })),
+    }
+  }
+
+  /// Returns whether the given function returns true for any value within the expression.
+  pub(crate) fn any_value<F: Copy + Fn(&V) -> bool>(&self, f: F) -> bool {
+    let any_vec = |args: &Vec<Calc<V>>| args.iter().any(|arg| arg.any_value(f));
+    match self {
+      Calc::Value(v) => f(v),
+      Calc::Number(_) => false,
+      Calc::Sum(a, b) => a.any_value(f) || b.any_value(f),
+      Calc::Product(_, calc) => calc.any_value(f),
+      Calc::Function(function) => match &**function {
+        MathFunction::Calc(c) | MathFunction::Abs(c) | MathFunction::Sign(c) => c.any_value(f),
+        MathFunction::Min(args) | MathFunction::Max(args) | MathFunction::Hypot(args) => any_vec(args),
+        MathFunction::Clamp(a, b, c) => a.any_value(f) || b.any_value(f) || c.any_value(f),
+        MathFunction::Round(_, a, b) | MathFunction::Rem(a, b) | MathFunction::Mod(a, b) => {
+          a.any_value(f) || b.any_value(f)
+        }
+      },
+    }
+  }
+
+  /// Resolves the expression to a single number, using the given function
+  /// to resolve each value within it (e.g. to convert lengths to pixels).
+  /// Returns `None` if any value cannot be resolved, or if the expression
+  /// does not resolve to the same type as its values.
+  pub(crate) fn resolve_with<F: Copy + Fn(&V) -> Option<f32>>(&self, f: F) -> Option<f32> {
+    match self {
+      Calc::Value(v) => f(v),
+      // A bare number within a sum of values is a type mismatch.
+      Calc::Number(_) => None,
+      Calc::Sum(a, b) => Some(a.resolve_with(f)? + b.resolve_with(f)?),
+      Calc::Product(num, calc) => Some(num * calc.resolve_with(f)?),
+      Calc::Function(function) => match &**function {
+        MathFunction::Calc(c) => c.resolve_with(f),
+        MathFunction::Min(args) => args
+          .iter()
+          .try_fold(f32::INFINITY, |acc, arg| Some(acc.min(arg.resolve_with(f)?))),
+        MathFunction::Max(args) => args
+          .iter()
+          .try_fold(f32::NEG_INFINITY, |acc, arg| Some(acc.max(arg.resolve_with(f)?))),
+        MathFunction::Clamp(min, center, max) => {
+          let min = min.resolve_with(f)?;
+          Some(min.max(center.resolve_with(f)?.min(max.resolve_with(f)?)))
+        }
+        MathFunction::Round(strategy, a, b) => Some(round(a.resolve_with(f)?, b.resolve_with(f)?, *strategy)),
+        MathFunction::Rem(a, b) => Some(a.resolve_with(f)? % b.resolve_with(f)?),
+        MathFunction::Mod(a, b) => Some(modulo(a.resolve_with(f)?, b.resolve_with(f)?)),
+        MathFunction::Abs(v) => Some(v.resolve_with(f)?.abs()),
+        MathFunction::Hypot(args) => {
+          let sum = args.iter().try_fold(0.0, |acc, arg| Some(acc + arg.resolve_with(f)?.powi(2)))?;
+          Some(sum.sqrt())
+        }
+        // sign() always resolves to a number.
+        MathFunction::Sign(_) => None,
+      },
+    }
+  }
+}
+
+impl<V: TryAdd<V>> Calc<V> {
+  /// Simplifies a sum by combining all terms of compatible units, regardless of
+  /// their position in the expression tree. For example, `1em + 2px + 3em` becomes `4em + 2px`.
+  /// Terms are kept in the order they first appear.
+  pub fn collect_terms(self) -> Calc<V> {
+    self.collect_terms_with(&mut |a: &V, b: &V| a.try_add(b))
+  }
+
+  /// Like [collect_terms](Calc::collect_terms), but combines values using the given function,
+  /// which returns `None` if the values cannot be added.
+  pub(crate) fn collect_terms_with<F: FnMut(&V, &V) -> Option<V>>(self, add: &mut F) -> Calc<V> {
+    let mut terms = Vec::new();
+    self.flatten_sum(&mut terms);
+
+    let mut collected: Vec<Calc<V>> = Vec::with_capacity(terms.len());
+    for term in terms {
+      let term = match term {
+        Calc::Product(num, calc) => Calc::Product(num, Box::new(calc.collect_terms_with(add))),
+        term => term,
+      };
+
+      let mut combined = None;
+      for (i, existing) in collected.iter().enumerate() {
+        let sum = match (existing, &term) {
+          (Calc::Value(a), Calc::Value(b)) => add(a, b).map(|v| Calc::Value(Box::new(v))),
+          (Calc::Number(a), Calc::Number(b)) => Some(Calc::Number(a + b)),
+          _ => None,
+        };
+        if let Some(sum) = sum {
+          combined = Some((i, sum));
+          break;
+        }
+      }
+
+      match combined {
+        Some((i, sum)) => collected[i] = sum,
+        None => collected.push(term),
+      }
+    }
+
+    let mut iter = collected.into_iter();
+    let first = iter.next().unwrap();
+    iter.fold(first, |acc, term| Calc::Sum(Box::new(acc), Box::new(term)))
+  }
+
+  fn flatten_sum(self, terms: &mut Vec<Calc<V>>) {
+    match self {
+      Calc::Sum(a, b) => {
+        a.flatten_sum(terms);
+        b.flatten_sum(terms);
+      }
+      Calc::Function(f) => match *f {
+        MathFunction::Calc(c) => c.flatten_sum(terms),
+        f => terms.push(Calc::Function(Box::new(f))),
+      },
+      term => terms.push(term),
+    }
+  }
+}
+
+impl<V: Zero> Calc<V> {
+  /// Removes identity operations from the expression, i.e. multiplication by one and
+  /// addition of zero. For example, `calc(5px * 1)` and `calc(5px + 0px)` both become `5px`.
+  /// A `calc()` wrapper is dropped once only a single value remains.
+  pub fn normalize(self) -> Calc<V> {
+    match self {
+      Calc::Product(num, calc) if num == 1.0 => calc.normalize(),
+      Calc::Product(num, calc) => Calc::Product(num, Box::new(calc.normalize())),
+      Calc::Sum(a, b) => match (a.normalize(), b.normalize()) {
+        (a, b) if b.is_additive_zero() => a,
+        (a, b) if a.is_additive_zero() => b,
+        (a, b) => Calc::Sum(Box::new(a), Box::new(b)),
+      },
+      Calc::Function(f) => match *f {
+        MathFunction::Calc(c) => match c.normalize() {
+          c @ (Calc::Value(_) | Calc::Number(_)) => c,
+          c => Calc::Function(Box::new(MathFunction::Calc(c))),
+        },
+        f => Calc::Function(Box::new(f)),
+      },
+      calc => calc,
+    }
+  }
+
+  fn is_additive_zero(&self) -> bool {
+    match self {
+      Calc::Value(v) => v.is_zero(),
+      Calc::Number(n) => *n == 0.0,
+      _ => false,
+    }
+  }
+}
+
+impl<V: std::fmt::Debug> Calc<V> {
+  /// Returns an indented tree describing the structure of the expression, with one node per line.
+  /// This is intended for debugging, and is distinct from the CSS serialization.
+  pub fn debug_tree(&self) -> String {
+    let mut tree = String::new();
+    self.write_debug_tree(&mut tree, 0);
+    tree
+  }
+
+  fn write_debug_tree(&self, tree: &mut String, depth: usize) {
+    use std::fmt::Write;
+    let _ = write!(tree, "{}", "  ".repeat(depth));
+    let args = match self {
+      Calc::Value(v) => {
+        let _ = writeln!(tree, "Value({:?})", v);
+        return;
+      }
+      Calc::Number(n) => {
+        let _ = writeln!(tree, "Number({})", n);
+        return;
+      }
+      Calc::Sum(a, b) => {
+        let _ = writeln!(tree, "Sum");
+        vec![&**a, &**b]
+      }
+      Calc::Product(num, calc) => {
+        let _ = writeln!(tree, "Product({})", num);
+        vec![&**calc]
+      }
+      Calc::Function(f) => {
+        let (name, args) = match &**f {
+          MathFunction::Calc(c) => ("calc".into(), vec![c]),
+          MathFunction::Min(args) => ("min".into(), args.iter().collect()),
+          MathFunction::Max(args) => ("max".into(), args.iter().collect()),
+          MathFunction::Clamp(a, b, c) => ("clamp".into(), vec![a, b, c]),
+          MathFunction::Round(strategy, a, b) => (format!("round, {}", strategy.as_str()), vec![a, b]),
+          MathFunction::Rem(a, b) => ("rem".into(), vec![a, b]),
+          MathFunction::Mod(a, b) => ("mod".into(), vec![a, b]),
+          MathFunction::Abs(a) => ("abs".into(), vec![a]),
+          MathFunction::Sign(a) => ("sign".into(), vec![a]),
+          MathFunction::Hypot(args) => ("hypot".into(), args.iter().collect()),
+        };
+        let _ = writeln!(tree, "Function({})", name);
+        args
+      }
+    };
+    for arg in args {
+      arg.write_debug_tree(tree, depth + 1);
+    }
+  }
+}
+
 impl<V: std::ops::Mul<f32, Output = V>> std::ops::Mul<f32> for Calc<V> {
   type Output = Self;
 
@@ -922,24 +1170,30 @@ impl<V: ToCss + std::ops::Mul<f32, Output = V> + TrySign + Clone + std::fmt::Deb
   where
     W: std::fmt::Write,
   {
+    if dest.disallow_calc && !matches!(self, Calc::Value(_) | Calc::Number(_)) {
+      return Err(dest.error_at_current_loc(PrinterErrorKind::CalcNotAllowed));
+    }
+
     let was_in_calc = dest.in_calc;
     dest.in_calc = true;
 
     let res = match self {
       Calc::Value(v) => v.to_css(dest),
       Calc::Number(n) => n.to_css(dest),
-      Calc::Sum(a, b) => {
-        a.to_css(dest)?;
-        // Whitespace is always required.
-        let b = &**b;
-        if b.is_sign_negative() {
-          dest.write_str(" - ")?;
-          let b = b.clone() * -1.0;
-          b.to_css(dest)
-        } else {
-          dest.write_str(" + ")?;
-          b.to_css(dest)
+      Calc::Sum(..) => {
+        let mut terms = Vec::new();
+        self.sum_terms(&mut terms);
+
+        // Lead with a positive term if there is one, e.g. `10px - 5em` rather than `-5em + 10px`.
+        if terms[0].is_sign_negative() {
+          if let Some(i) = terms.iter().position(|term| term.is_sign_positive()) {
+            let term = terms.remove(i);
+            terms.insert(0, term);
+          }
         }
+
+        terms[0].to_css(dest)?;
+        terms[1..].iter().try_for_each(|term| term.write_sum_term(dest))
       }
       Calc::Product(num, calc) => {
         if num.abs() < 1.0 {
@@ -961,6 +1215,36 @@ impl<V: ToCss + std::ops::Mul<f32, Output = V> + TrySign + Clone + std::fmt::Deb
   }
 }
 
+impl<V: ToCss + std::ops::Mul<f32, Output = V> + TrySign + Clone + std::fmt::Debug> Calc<V> {
+  /// Collects the terms of a sum, flattening nested sums so that each term is printed with
+  /// its own operator, e.g. `a - b - c` rather than `a + -b - c`.
+  fn sum_terms<'a>(&'a self, terms: &mut Vec<&'a Calc<V>>) {
+    match self {
+      Calc::Sum(a, b) => {
+        a.sum_terms(terms);
+        b.sum_terms(terms);
+      }
+      term => terms.push(term),
+    }
+  }
+
+  /// Writes a term following another in a sum, along with the operator.
+  fn write_sum_term<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
+  where
+    W: std::fmt::Write,
+  {
+    // Whitespace is always required.
+    if self.is_sign_negative() {
+      dest.write_str(" - ")?;
+      let b = self.clone() * -1.0;
+      b.to_css(dest)
+    } else {
+      dest.write_str(" + ")?;
+      self.to_css(dest)
+    }
+  }
+}
+
 impl<V: TrySign> TrySign for Calc<V> {
   fn try_sign(&self) -> Option<f32> {
     match self {
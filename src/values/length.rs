@@ -1,11 +1,13 @@
 //! CSS length values.
 
 use super::angle::impl_try_from_angle;
-use super::calc::{Calc, MathFunction};
+use super::calc::{is_math_function, Calc, MathFunction};
 use super::number::CSSNumber;
-use super::percentage::DimensionPercentage;
-use crate::error::{ParserError, PrinterError};
-use crate::printer::Printer;
+use super::percentage::{DimensionPercentage, Percentage};
+use super::string::CowArcStr;
+use crate::error::{ParserError, PrinterError, PrinterErrorKind};
+use crate::printer::{Printer, PrinterOptions};
+use crate::properties::writing_modes::WritingMode;
 use crate::targets::Browsers;
 use crate::traits::{
   private::{AddInternal, TryAdd},
@@ -27,6 +29,166 @@ impl LengthPercentage {
     LengthPercentage::Dimension(LengthValue::Px(val))
   }
 
+  /// Parses a length or percentage, rejecting negative values, e.g. for `gap`.
+  /// Only explicit values are checked, because the sign of a `calc()` expression may
+  /// not be known until it is resolved.
+  pub fn parse_non_negative<'i>(
+    input: &mut Parser<'i, '_>,
+  ) -> Result<LengthPercentage, ParseError<'i, ParserError<'i>>> {
+    let location = input.current_source_location();
+    let value = LengthPercentage::parse(input)?;
+    let negative = match &value {
+      DimensionPercentage::Dimension(d) => d.to_unit_value().0 < 0.0,
+      DimensionPercentage::Percentage(p) => p.0 < 0.0,
+      DimensionPercentage::Calc(_) => false,
+    };
+    if negative {
+      return Err(location.new_custom_error(ParserError::NegativeValue));
+    }
+    Ok(value)
+  }
+
+  /// Returns whether the value is an explicit length.
+  pub fn is_length(&self) -> bool {
+    matches!(self, DimensionPercentage::Dimension(_))
+  }
+
+  /// Returns whether the value is an explicit percentage.
+  pub fn is_percentage(&self) -> bool {
+    matches!(self, DimensionPercentage::Percentage(_))
+  }
+
+  /// Returns whether the value is a `calc()` expression.
+  pub fn is_calc(&self) -> bool {
+    matches!(self, DimensionPercentage::Calc(_))
+  }
+
+  /// Returns whether the value is exactly `100%`.
+  pub fn is_full_percentage(&self) -> bool {
+    matches!(self, DimensionPercentage::Percentage(Percentage(p)) if *p == 1.0)
+  }
+
+  /// Attempts to resolve the value to pixels, using the given context to resolve
+  /// relative lengths, and resolving percentages against the given reference size in pixels.
+  /// Returns `None` if the context does not contain the information needed to resolve the value.
+  pub fn to_px_with(&self, ctx: &LengthContext, reference: CSSNumber) -> Option<CSSNumber> {
+    match self {
+      DimensionPercentage::Dimension(d) => d.to_px_with(ctx),
+      DimensionPercentage::Percentage(p) => Some(p.0 * reference),
+      DimensionPercentage::Calc(c) => c.resolve_with(|v| v.to_px_with(ctx, reference)),
+    }
+  }
+
+  /// Returns whether the value is a `calc()` expression containing only percentages,
+  /// e.g. `calc(50% + 25%)` before it is simplified.
+  pub fn is_percentage_calc(&self) -> bool {
+    fn is_percentage(value: &LengthPercentage) -> bool {
+      match value {
+        DimensionPercentage::Percentage(_) => true,
+        DimensionPercentage::Calc(c) => !c.any_value(|v| !is_percentage(v)),
+        DimensionPercentage::Dimension(_) => false,
+      }
+    }
+
+    matches!(self, DimensionPercentage::Calc(_)) && is_percentage(self)
+  }
+
+  /// Returns a key in pixels for ordering values such as gradient stop positions, with
+  /// percentages resolved against the given reference size. Values that cannot be resolved
+  /// return `None`, and should be sorted after all others so their relative order is kept.
+  pub fn sort_key(&self, ctx: &LengthContext, reference: CSSNumber) -> Option<CSSNumber> {
+    self.to_px_with(ctx, reference).filter(|px| !px.is_nan())
+  }
+
+  /// Splits the value into its length and percentage parts, e.g. `calc(10px + 50%)` into
+  /// `10px` and `50%`. Parts that are missing are zero. Returns `None` if the value is a
+  /// `calc()` expression that is not a simple sum of lengths and percentages.
+  pub fn split(&self) -> Option<(Length, Percentage)> {
+    match self.decompose() {
+      (length, percentage, None) => Some((length, percentage)),
+      _ => None,
+    }
+  }
+
+  /// Decomposes the value into the sum of its length terms, the sum of its percentage terms,
+  /// and any remaining terms that are neither, such as `min()` or `clamp()` functions. For
+  /// example, `calc(10px + 50% + min(1em, 2vw))` decomposes into `10px`, `50%`, and
+  /// `min(1em, 2vw)`. Unlike [split](LengthPercentage::split), this never fails.
+  pub fn decompose(&self) -> (Length, Percentage, Option<Calc<LengthPercentage>>) {
+    fn decompose_calc(
+      calc: &Calc<LengthPercentage>,
+      length: &mut Length,
+      percentage: &mut Percentage,
+      residual: &mut Option<Calc<LengthPercentage>>,
+    ) {
+      match calc {
+        Calc::Value(v) => match &**v {
+          DimensionPercentage::Dimension(d) => {
+            *length = std::mem::replace(length, Length::zero()) + Length::Value(d.clone())
+          }
+          DimensionPercentage::Percentage(p) => percentage.0 += p.0,
+          DimensionPercentage::Calc(c) => decompose_calc(c, length, percentage, residual),
+        },
+        Calc::Sum(a, b) => {
+          decompose_calc(a, length, percentage, residual);
+          decompose_calc(b, length, percentage, residual);
+        }
+        Calc::Function(f) => match &**f {
+          MathFunction::Calc(c) => decompose_calc(c, length, percentage, residual),
+          _ => add_residual(calc, residual),
+        },
+        _ => add_residual(calc, residual),
+      }
+    }
+
+    fn add_residual(calc: &Calc<LengthPercentage>, residual: &mut Option<Calc<LengthPercentage>>) {
+      *residual = Some(match residual.take() {
+        Some(r) => Calc::Sum(Box::new(r), Box::new(calc.clone())),
+        None => calc.clone(),
+      })
+    }
+
+    match self {
+      DimensionPercentage::Dimension(d) => (Length::Value(d.clone()), Percentage(0.0), None),
+      DimensionPercentage::Percentage(p) => (Length::zero(), p.clone(), None),
+      DimensionPercentage::Calc(c) => {
+        let mut length = Length::zero();
+        let mut percentage = Percentage(0.0);
+        let mut residual = None;
+        decompose_calc(c, &mut length, &mut percentage, &mut residual);
+        (length, percentage, residual)
+      }
+    }
+  }
+
+  /// Replaces percentages with the given length multiplied by their fraction, recursing into
+  /// `calc()` expressions, for use when the value that 100% refers to is known. Sums are folded
+  /// where possible, so for example baking `calc(50% + 10px)` against `200px` gives `110px`.
+  pub fn bake_percentage(&self, hundred_percent: Length) -> LengthPercentage {
+    fn bake(value: &LengthPercentage, hundred: &LengthPercentage) -> LengthPercentage {
+      match value {
+        DimensionPercentage::Dimension(_) => value.clone(),
+        DimensionPercentage::Percentage(p) => hundred.clone() * p.0,
+        DimensionPercentage::Calc(c) => bake_calc(c, hundred),
+      }
+    }
+
+    fn bake_calc(calc: &Calc<LengthPercentage>, hundred: &LengthPercentage) -> LengthPercentage {
+      match calc {
+        Calc::Value(v) => bake(v, hundred),
+        Calc::Sum(a, b) => bake_calc(a, hundred) + bake_calc(b, hundred),
+        Calc::Product(num, c) => bake_calc(c, hundred) * *num,
+        Calc::Function(f) => match &**f {
+          MathFunction::Calc(c) => bake_calc(c, hundred),
+          _ => DimensionPercentage::Calc(Box::new(calc.map_values(|v| bake(v, hundred)))),
+        },
+        Calc::Number(_) => DimensionPercentage::Calc(Box::new(calc.map_values(|v| bake(v, hundred)))),
+      }
+    }
+
+    bake(self, &LengthPercentage::from(hundred_percent))
+  }
+
   pub(crate) fn to_css_unitless<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
   where
     W: std::fmt::Write,
@@ -38,6 +200,15 @@ impl LengthPercentage {
   }
 }
 
+impl From<Length> for LengthPercentage {
+  fn from(length: Length) -> LengthPercentage {
+    match length {
+      Length::Value(v) => LengthPercentage::Dimension(v),
+      Length::Calc(c) => LengthPercentage::Calc(Box::new(c.map_values(|v| v.clone().into()))),
+    }
+  }
+}
+
 impl IsCompatible for LengthPercentage {
   fn is_compatible(&self, browsers: Browsers) -> bool {
     match self {
@@ -67,6 +238,7 @@ pub enum LengthPercentageOrAuto {
 
 impl<'i> Parse<'i> for LengthPercentageOrAuto {
   fn parse<'t>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i, ParserError<'i>>> {
+    // `auto` is not valid within calc(), so it is only accepted as a bare keyword.
     if input.try_parse(|i| i.expect_ident_matching("auto")).is_ok() {
       return Ok(LengthPercentageOrAuto::Auto);
     }
@@ -101,6 +273,10 @@ impl IsCompatible for LengthPercentageOrAuto {
   }
 }
 
+/// The largest integer that lengths can represent exactly. Lengths are stored as `f32`, so
+/// larger integers may be rounded when parsed, e.g. `16777217px` is stored as `16777216px`.
+pub const MAX_SAFE_INTEGER: CSSNumber = 16777216.0;
+
 const PX_PER_IN: f32 = 96.0;
 const PX_PER_CM: f32 = PX_PER_IN / 2.54;
 const PX_PER_MM: f32 = PX_PER_CM / 10.0;
@@ -129,23 +305,36 @@ macro_rules! define_length_units {
       )+
     }
 
+    /// A length unit, without a numeric value. See also: [LengthValue](LengthValue).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum LengthUnit {
+      $(
+        $(#[$meta])*
+        $name,
+      )+
+    }
+
     impl<'i> Parse<'i> for LengthValue {
       fn parse<'t>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i, ParserError<'i>>> {
         let location = input.current_source_location();
         let token = input.next()?;
         match *token {
+          // Values that overflow f32 (e.g. `1e39px`) are rejected rather than stored as infinity.
+          Token::Dimension { value, .. } | Token::Number { value, .. } if !value.is_finite() => {
+            Err(location.new_custom_error(ParserError::InvalidValue))
+          }
           Token::Dimension { value, ref unit, .. } => {
-            Ok(match unit {
-              $(
-                s if s.eq_ignore_ascii_case(stringify!($name)) => LengthValue::$name(value),
-              )+
-              _ => return Err(location.new_unexpected_token_error(token.clone())),
-            })
+            match LengthValue::from_unit(value, unit) {
+              Some(v) => Ok(v),
+              None => Err(location.new_unexpected_token_error(token.clone())),
+            }
           },
           Token::Number { value, .. } => {
             // TODO: quirks mode only?
             Ok(LengthValue::Px(value))
           }
+          // Only ASCII digits form numeric tokens. Lookalikes such as full-width digits
+          // (e.g. `１０px`) are tokenized as identifiers, and rejected here.
           ref token => return Err(location.new_unexpected_token_error(token.clone())),
         }
       }
@@ -156,13 +345,8 @@ macro_rules! define_length_units {
 
       fn try_from(token: &Token) -> Result<Self, Self::Error> {
         match token {
-          Token::Dimension { value, ref unit, .. } => {
-            Ok(match unit {
-              $(
-                s if s.eq_ignore_ascii_case(stringify!($name)) => LengthValue::$name(*value),
-              )+
-              _ => return Err(()),
-            })
+          Token::Dimension { value, ref unit, .. } if value.is_finite() => {
+            LengthValue::from_unit(*value, unit).ok_or(())
           },
           _ => Err(())
         }
@@ -170,14 +354,57 @@ macro_rules! define_length_units {
     }
 
     impl LengthValue {
+      /// Returns a length value with the given numeric value for every unit.
+      #[cfg(test)]
+      pub(crate) fn all_units(value: CSSNumber) -> Vec<LengthValue> {
+        vec![$(LengthValue::$name(value)),+]
+      }
+
+      /// Constructs a length value from a numeric value and a unit.
+      pub fn new(value: CSSNumber, unit: LengthUnit) -> LengthValue {
+        match unit {
+          $(
+            LengthUnit::$name => LengthValue::$name(value),
+          )+
+        }
+      }
+
+      /// Returns the unit of the length value.
+      pub fn unit(&self) -> LengthUnit {
+        match self {
+          $(
+            LengthValue::$name(_) => LengthUnit::$name,
+          )+
+        }
+      }
+
+      /// Constructs a length value from a numeric value and a unit string.
+      /// Units are matched case-insensitively. Returns `None` if the unit is unknown.
+      pub fn from_unit(value: CSSNumber, unit: &str) -> Option<LengthValue> {
+        Some(match unit {
+          $(
+            s if s.eq_ignore_ascii_case(stringify!($name)) => LengthValue::$name(value),
+          )+
+          _ => return None,
+        })
+      }
+
       /// Returns the numeric value and unit string for the length value.
-      pub fn to_unit_value(&self) -> (CSSNumber, &str) {
+      pub fn to_unit_value(&self) -> (CSSNumber, &'static str) {
         match self {
           $(
             LengthValue::$name(value) => (*value, const_str::convert_ascii_case!(lower, stringify!($name))),
           )+
         }
       }
+
+      /// Returns a length value with the same numeric value, but in the given unit.
+      /// Unlike a conversion, the magnitude is preserved, e.g. `10px` becomes `10pt`.
+      /// Returns `None` if the unit is unknown.
+      pub fn with_unit(&self, unit: &str) -> Option<LengthValue> {
+        let (value, _) = self.to_unit_value();
+        LengthValue::from_unit(value, unit)
+      }
     }
 
     impl IsCompatible for LengthValue {
@@ -483,18 +710,65 @@ define_length_units! {
 
 impl ToCss for LengthValue {
   fn to_css<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
+  where
+    W: std::fmt::Write,
+  {
+    if dest.minify && dest.minify_absolute_lengths && !dest.in_custom_property {
+      let minified = self.minify_absolute();
+      if minified.unit() != self.unit() {
+        return minified.write_value(dest);
+      }
+    }
+
+    self.write_value(dest)
+  }
+}
+
+impl LengthValue {
+  fn write_value<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
   where
     W: std::fmt::Write,
   {
     let (value, unit) = self.to_unit_value();
 
+    // Non-finite lengths can result from `infinity` or `NaN` in calc(), but have no direct
+    // serialization, so they are written as a calc() expression, e.g. `calc(infinity * 1px)`.
+    if !value.is_finite() {
+      if dest.disallow_calc {
+        return Err(dest.error_at_current_loc(PrinterErrorKind::CalcNotAllowed));
+      }
+
+      dest.write_str("calc(")?;
+      if value.is_nan() {
+        dest.write_str("NaN")?;
+      } else if value < 0.0 {
+        dest.write_str("-infinity")?;
+      } else {
+        dest.write_str("infinity")?;
+      }
+      dest.delim('*', true)?;
+      let was_in_calc = dest.in_calc;
+      dest.in_calc = true;
+      let res = match dest.unit_rewrite {
+        Some(rewrite) => serialize_dimension(1.0, &rewrite(unit), dest),
+        None => serialize_dimension(1.0, unit, dest),
+      };
+      dest.in_calc = was_in_calc;
+      res?;
+      return dest.write_char(')');
+    }
+
     // The unit can be omitted if the value is zero, except inside calc()
-    // expressions, where unitless numbers won't be parsed as dimensions.
-    if !dest.in_calc && value == 0.0 {
+    // expressions, where unitless numbers won't be parsed as dimensions. Absolute lengths
+    // below the zero threshold are treated as zero.
+    if !dest.in_calc && (value == 0.0 || self.to_px().map_or(false, |px| px.abs() < dest.zero_threshold)) {
       return dest.write_char('0');
     }
 
-    serialize_dimension(value, unit, dest)
+    match dest.unit_rewrite {
+      Some(rewrite) => serialize_length(value, &rewrite(unit), dest),
+      None => serialize_length(value, unit, dest),
+    }
   }
 }
 
@@ -510,27 +784,79 @@ impl LengthValue {
   }
 }
 
+/// Serializes a length with the given unit, applying the printer's length-specific options.
+pub(crate) fn serialize_length<W>(value: f32, unit: &str, dest: &mut Printer<W>) -> Result<(), PrinterError>
+where
+  W: std::fmt::Write,
+{
+  // A sign within calc() would be redundant with the surrounding operator. Custom
+  // properties are written as authored, since they may be substituted anywhere via var().
+  let explicit_sign = dest.explicit_sign && !dest.in_calc && !dest.in_custom_property && value > 0.0;
+  let trailing_zero = dest.keep_trailing_zero && !dest.in_custom_property;
+  write_dimension(value, unit, explicit_sign, trailing_zero, dest)
+}
+
 pub(crate) fn serialize_dimension<W>(value: f32, unit: &str, dest: &mut Printer<W>) -> Result<(), PrinterError>
+where
+  W: std::fmt::Write,
+{
+  write_dimension(value, unit, false, false, dest)
+}
+
+fn write_dimension<W>(
+  value: f32,
+  unit: &str,
+  explicit_sign: bool,
+  trailing_zero: bool,
+  dest: &mut Printer<W>,
+) -> Result<(), PrinterError>
 where
   W: std::fmt::Write,
 {
   use cssparser::ToCss;
   let int_value = if value.fract() == 0.0 { Some(value as i32) } else { None };
   let token = Token::Dimension {
-    has_sign: value < 0.0,
+    has_sign: value < 0.0 || explicit_sign,
     value,
     int_value,
     unit: CowRcStr::from(unit),
   };
   if value != 0.0 && value.abs() < 1.0 {
-    let mut s = String::new();
-    token.to_css(&mut s)?;
-    if value < 0.0 {
-      dest.write_char('-')?;
-      dest.write_str(s.trim_start_matches("-0"))
+    // Borrow the printer's scratch buffer to avoid allocating a string for each value.
+    let mut s = std::mem::take(&mut dest.scratch);
+    s.clear();
+    let res = token.to_css(&mut s).map_err(PrinterError::from).and_then(|_| {
+      if value < 0.0 {
+        dest.write_char('-')?;
+        dest.write_str(s.trim_start_matches("-0"))
+      } else if explicit_sign {
+        dest.write_char('+')?;
+        dest.write_str(s.trim_start_matches("+0"))
+      } else {
+        dest.write_str(s.trim_start_matches('0'))
+      }
+    });
+    dest.scratch = s;
+    res
+  } else if let Some(int_value) = int_value.filter(|_| value != 0.0 && value.abs() < 2147483648.0) {
+    // cssparser limits the precision of serialized numbers, which rounds large integers
+    // (e.g. 123456789px to 123457000px), so write integers exactly.
+    use std::fmt::Write;
+    if explicit_sign {
+      dest.write_char('+')?;
+    }
+    write!(dest, "{}", int_value)?;
+    if trailing_zero {
+      dest.write_str(".0")?;
+    }
+    // Disambiguate with scientific notation, as cssparser does.
+    if unit == "e" || unit == "E" || unit.starts_with("e-") || unit.starts_with("E-") {
+      dest.write_str("\\65 ")?;
+      serialize_name(&unit[1..], dest)?;
     } else {
-      dest.write_str(s.trim_start_matches('0'))
+      serialize_identifier(unit, dest)?;
     }
+    Ok(())
   } else {
     token.to_css(dest)?;
     Ok(())
@@ -553,6 +879,380 @@ impl LengthValue {
       _ => None,
     }
   }
+
+  /// Attempts to convert the value to pixels, using the given context to resolve relative units.
+  /// Returns `None` if the context does not contain the information needed to resolve the unit.
+  pub fn to_px_with(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    self.to_px().or_else(|| self.resolve_relative(ctx))
+  }
+
+  /// Resolves a relative length to pixels using the given context.
+  /// Returns `None` if the length is absolute, or if the context does not contain
+  /// the information needed to resolve the unit, e.g. for container-relative units.
+  /// See [to_px_with](LengthValue::to_px_with) to resolve both absolute and relative lengths.
+  pub fn resolve_relative(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    use LengthValue::*;
+    match self {
+      Em(value) => Some(value * ctx.font_size?),
+      Rem(value) => Some(value * ctx.root_font_size?),
+      Ex(value) => Some(value * ctx.x_height?),
+      Ch(value) => Some(value * ctx.ch_width?),
+      Vw(value) => Some(value * ctx.viewport_width? / 100.0),
+      Vh(value) => Some(value * ctx.viewport_height? / 100.0),
+      Vmin(value) => Some(value * ctx.viewport_width?.min(ctx.viewport_height?) / 100.0),
+      Vmax(value) => Some(value * ctx.viewport_width?.max(ctx.viewport_height?) / 100.0),
+      _ => None,
+    }
+  }
+
+  /// Returns whether the length can only be resolved with information about the layout,
+  /// i.e. it uses a font-relative, viewport-relative, or container-relative unit.
+  pub fn needs_layout(&self) -> bool {
+    self.to_px().is_none()
+  }
+
+  /// Returns whether the length uses a viewport-relative unit, e.g. `vw`, `svh`, or `dvmin`.
+  pub fn is_viewport_relative(&self) -> bool {
+    use LengthValue::*;
+    matches!(
+      self,
+      Vw(_)
+        | Lvw(_)
+        | Svw(_)
+        | Dvw(_)
+        | Vh(_)
+        | Lvh(_)
+        | Svh(_)
+        | Dvh(_)
+        | Vi(_)
+        | Svi(_)
+        | Lvi(_)
+        | Dvi(_)
+        | Vb(_)
+        | Svb(_)
+        | Lvb(_)
+        | Dvb(_)
+        | Vmin(_)
+        | Svmin(_)
+        | Lvmin(_)
+        | Dvmin(_)
+        | Vmax(_)
+        | Svmax(_)
+        | Lvmax(_)
+        | Dvmax(_)
+    )
+  }
+
+  /// Returns the kind of unit used by the length.
+  pub fn unit_kind(&self) -> LengthUnitKind {
+    use LengthValue::*;
+    match self {
+      Px(_) | In(_) | Cm(_) | Mm(_) | Q(_) | Pt(_) | Pc(_) => LengthUnitKind::Absolute,
+      Em(_) | Rem(_) | Ex(_) | Rex(_) | Ch(_) | Rch(_) | Cap(_) | Rcap(_) | Ic(_) | Ric(_) | Lh(_) | Rlh(_) => {
+        LengthUnitKind::FontRelative
+      }
+      Cqw(_) | Cqh(_) | Cqi(_) | Cqb(_) | Cqmin(_) | Cqmax(_) => LengthUnitKind::ContainerRelative,
+      _ => LengthUnitKind::ViewportRelative,
+    }
+  }
+
+  /// Returns each absolute length unit, along with the number of pixels per unit.
+  pub fn absolute_units() -> &'static [(&'static str, CSSNumber)] {
+    &ABSOLUTE_UNIT_FACTORS
+  }
+
+  /// Attempts to convert the value to pixels with double precision, which avoids accumulating
+  /// rounding errors across repeated conversions. Returns `None` if the length is not absolute.
+  pub fn to_px_f64(&self) -> Option<f64> {
+    let (value, _) = self.to_unit_value();
+    Some(value as f64 * self.px_per_unit()?)
+  }
+
+  /// Returns the number of pixels in this length, i.e. its ratio to `1px`.
+  /// Returns `None` if the length is not absolute.
+  pub fn ratio_to_px(&self) -> Option<CSSNumber> {
+    self.to_px()
+  }
+
+  /// Returns the ratio between this length and another, e.g. `96` for `1in` and `1px`.
+  /// Returns `None` if either length is not absolute, or if the ratio is not finite
+  /// (e.g. when the other length is zero).
+  pub fn ratio(&self, other: &LengthValue) -> Option<CSSNumber> {
+    let ratio = self.to_px()? / other.to_px()?;
+    if ratio.is_finite() {
+      Some(ratio)
+    } else {
+      None
+    }
+  }
+
+  /// Returns the difference between this length and a base length as a fraction of the base,
+  /// e.g. `0.2` for `120px` and `100px`. Returns `None` if either length is not absolute,
+  /// or if the base is zero.
+  pub fn percent_diff(&self, base: &LengthValue) -> Option<CSSNumber> {
+    let base = base.to_px()?;
+    let diff = (self.to_px()? - base) / base;
+    if diff.is_finite() {
+      Some(diff)
+    } else {
+      None
+    }
+  }
+
+  /// Adds another length to this one in the same way as within `calc()`, and records a note
+  /// when the result has a different unit than both inputs. This happens when absolute lengths
+  /// with different units are added, e.g. `1in + 1cm`, which are converted to pixels.
+  /// Returns `None` if the lengths cannot be added without `calc()`.
+  pub fn try_add_with_notes(
+    &self,
+    other: &LengthValue,
+    notes: &mut Vec<UnitConversionNote>,
+  ) -> Option<LengthValue> {
+    let result = self.try_add(other)?;
+    let unit = result.unit();
+    let (left, right) = (self.unit(), other.unit());
+    if unit != left && unit != right {
+      // Converting to pixels is lossy if either operand has no exact representation in pixels.
+      let lossy = [self, other]
+        .iter()
+        .any(|v| v.to_px_f64().map_or(false, |px| (px as CSSNumber) as f64 != px));
+      notes.push(UnitConversionNote {
+        left,
+        right,
+        result: unit,
+        lossy,
+      });
+    }
+    Some(result)
+  }
+
+  /// Returns the shortest equivalent form of an absolute length, converting between absolute
+  /// units (e.g. `12pt` to `1pc`, or `96px` to `1in`) only when the conversion is lossless.
+  /// Fractional values are considered in every unit, so `19.2px` becomes `.2in`.
+  /// Relative lengths, and lengths with no shorter lossless form, are returned unchanged.
+  pub fn minify_absolute(&self) -> LengthValue {
+    let (value, _) = self.to_unit_value();
+    let factor = match self.px_per_unit() {
+      Some(factor) if value != 0.0 && value.is_finite() => factor,
+      _ => return self.clone(),
+    };
+
+    // Convert the shortest decimal form of the value rather than its binary approximation,
+    // so that e.g. `1.2px` is recognized as exactly `0.9pt`.
+    let exact: f64 = value.to_string().parse().unwrap();
+    let px = exact * factor;
+    let mut best = None;
+    let mut best_len = match minified_number_len(exact) {
+      Some(len) => len + self.to_unit_value().1.len(),
+      None => return self.clone(),
+    };
+    for unit in ABSOLUTE_UNITS {
+      let to = unit.px_per_unit().unwrap();
+      // Limit the precision of the converted value so that it serializes exactly,
+      // and ensure that converting back produces the original value.
+      let converted = (px / to * 1e4).round() / 1e4;
+      if (converted * to / factor - exact).abs() > exact.abs() * 1e-9 {
+        continue;
+      }
+
+      let len = match minified_number_len(converted) {
+        Some(len) => len + unit.to_unit_value().1.len(),
+        None => continue,
+      };
+      if len < best_len {
+        best = Some((unit, converted));
+        best_len = len;
+      }
+    }
+
+    match best {
+      Some((unit, converted)) => unit.map(|_| converted as f32),
+      None => self.clone(),
+    }
+  }
+
+  /// Returns an absolute length with its value rounded to the nearest integer in the same unit,
+  /// e.g. `10.6px` becomes `11px`. Relative lengths are returned unchanged.
+  pub fn round(&self) -> LengthValue {
+    match self.px_per_unit() {
+      Some(_) => self.map(|value| value.round()),
+      None => self.clone(),
+    }
+  }
+
+  /// Returns the number of pixels per unit for absolute lengths, with double precision.
+  fn px_per_unit(&self) -> Option<f64> {
+    use LengthValue::*;
+    match self {
+      Px(_) => Some(1.0),
+      In(_) => Some(96.0),
+      Cm(_) => Some(96.0 / 2.54),
+      Mm(_) => Some(96.0 / 25.4),
+      Q(_) => Some(96.0 / 101.6),
+      Pt(_) => Some(96.0 / 72.0),
+      Pc(_) => Some(96.0 / 6.0),
+      _ => None,
+    }
+  }
+}
+
+/// Returns the number of characters in the minified serialization of a number, e.g. 3 for `0.25`,
+/// which is printed as `.25`. Returns `None` if the number would not be printed exactly, because
+/// it has more than four decimal places, more than six significant digits, or is too large to be
+/// printed as an integer.
+fn minified_number_len(value: f64) -> Option<usize> {
+  if value.abs() >= 2147483648.0 {
+    return None;
+  }
+
+  let scaled = value.abs() * 1e4;
+  if (scaled - scaled.round()).abs() > 1e-3 {
+    return None;
+  }
+
+  let scaled = scaled.round() as u64;
+  let (int, mut fract) = (scaled / 10_000, scaled % 10_000);
+  let int_digits = int.checked_ilog10().map_or(0, |digits| digits as usize + 1);
+  let mut len = if value < 0.0 { 1 } else { 0 };
+  if fract == 0 {
+    return Some(len + int_digits.max(1));
+  }
+
+  let mut decimals = 4;
+  while fract % 10 == 0 {
+    fract /= 10;
+    decimals -= 1;
+  }
+  // Non-integers are printed with six significant digits.
+  let significant = if int > 0 {
+    int_digits + decimals
+  } else {
+    fract.ilog10() as usize + 1
+  };
+  if significant > 6 {
+    return None;
+  }
+
+  len += int_digits + 1 + decimals;
+  Some(len)
+}
+
+/// The absolute length units and the number of pixels per unit.
+const ABSOLUTE_UNIT_FACTORS: [(&str, CSSNumber); 7] = [
+  ("px", 1.0),
+  ("in", PX_PER_IN),
+  ("cm", PX_PER_CM),
+  ("mm", PX_PER_MM),
+  ("q", PX_PER_Q),
+  ("pt", PX_PER_PT),
+  ("pc", PX_PER_PC),
+];
+
+/// The absolute length units, in order of preference when multiple units have the same length.
+const ABSOLUTE_UNITS: [LengthValue; 7] = [
+  LengthValue::Px(1.0),
+  LengthValue::In(1.0),
+  LengthValue::Cm(1.0),
+  LengthValue::Mm(1.0),
+  LengthValue::Q(1.0),
+  LengthValue::Pt(1.0),
+  LengthValue::Pc(1.0),
+];
+
+/// A note that combining two lengths produced a value in a different unit than either input,
+/// recorded by [LengthValue::try_add_with_notes](LengthValue::try_add_with_notes).
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitConversionNote {
+  /// The unit of the left operand.
+  pub left: LengthUnit,
+  /// The unit of the right operand.
+  pub right: LengthUnit,
+  /// The unit of the result.
+  pub result: LengthUnit,
+  /// Whether the result had to be rounded, because an operand has no exact value in the unit
+  /// of the result, e.g. `1cm` in pixels. Counting these notes can be used to estimate how much
+  /// the values in a stylesheet drift when lengths are folded together.
+  pub lossy: bool,
+}
+
+/// Information about the environment in which a length is used, which is needed
+/// to resolve relative lengths to pixels. All sizes are in pixels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LengthContext {
+  /// The computed font size of the element, used to resolve `em` units.
+  pub font_size: Option<CSSNumber>,
+  /// The computed font size of the root element, used to resolve `rem` units.
+  pub root_font_size: Option<CSSNumber>,
+  /// The x-height of the element's font, used to resolve `ex` units.
+  pub x_height: Option<CSSNumber>,
+  /// The advance width of the `0` glyph in the element's font, used to resolve `ch` units.
+  pub ch_width: Option<CSSNumber>,
+  /// The width of the viewport, used to resolve `vw`, `vmin`, and `vmax` units.
+  pub viewport_width: Option<CSSNumber>,
+  /// The height of the viewport, used to resolve `vh`, `vmin`, and `vmax` units.
+  pub viewport_height: Option<CSSNumber>,
+  /// The size that percentages are resolved against, e.g. the width of the containing block.
+  pub percentage_basis: Option<CSSNumber>,
+  /// The range of viewport widths, as `(min, max)`, used by [Length::min_px](Length::min_px)
+  /// and [Length::max_px](Length::max_px). Overrides `viewport_width` when set.
+  pub viewport_width_range: Option<(CSSNumber, CSSNumber)>,
+  /// The range of viewport heights, as `(min, max)`, used by [Length::min_px](Length::min_px)
+  /// and [Length::max_px](Length::max_px). Overrides `viewport_height` when set.
+  pub viewport_height_range: Option<(CSSNumber, CSSNumber)>,
+}
+
+impl LengthContext {
+  /// Returns a context for each combination of the bounds of the viewport ranges.
+  fn viewport_bounds(&self) -> Vec<LengthContext> {
+    let bounds = |range: Option<(CSSNumber, CSSNumber)>, value: Option<CSSNumber>| match range {
+      Some((min, max)) => vec![Some(min), Some(max)],
+      None => vec![value],
+    };
+    let mut contexts = Vec::new();
+    for width in bounds(self.viewport_width_range, self.viewport_width) {
+      for height in bounds(self.viewport_height_range, self.viewport_height) {
+        contexts.push(LengthContext {
+          viewport_width: width,
+          viewport_height: height,
+          ..self.clone()
+        });
+      }
+    }
+    contexts
+  }
+}
+
+/// A trait for values that can be resolved to a length in pixels, given a [LengthContext](LengthContext).
+pub trait ResolveLength {
+  /// Attempts to resolve the value to pixels.
+  /// Returns `None` if the value cannot be resolved using the given context
+  /// (e.g. a relative unit with no corresponding size, or the `auto` keyword).
+  fn resolve(&self, ctx: &LengthContext) -> Option<CSSNumber>;
+}
+
+impl ResolveLength for Length {
+  fn resolve(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    self.to_px_with(ctx)
+  }
+}
+
+impl ResolveLength for LengthPercentage {
+  fn resolve(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    match self {
+      DimensionPercentage::Dimension(d) => d.to_px_with(ctx),
+      DimensionPercentage::Percentage(p) => Some(p.0 * ctx.percentage_basis?),
+      DimensionPercentage::Calc(c) => c.resolve_with(|v| v.resolve(ctx)),
+    }
+  }
+}
+
+impl ResolveLength for LengthPercentageOrAuto {
+  fn resolve(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    match self {
+      LengthPercentageOrAuto::Auto => None,
+      LengthPercentageOrAuto::LengthPercentage(l) => l.resolve(ctx),
+    }
+  }
 }
 
 /// A CSS [`<length>`](https://www.w3.org/TR/css-values-4/#lengths) value, with support for `calc()`.
@@ -644,7 +1344,7 @@ fn unwrap_calc(length: Length) -> Length {
 
 impl AddInternal for Length {
   fn add(self, other: Self) -> Self {
-    match self.try_add(&other) {
+    match TryAdd::try_add(&self, &other) {
       Some(r) => r,
       None => self.add(other),
     }
@@ -657,6 +1357,76 @@ impl Length {
     Length::Value(LengthValue::Px(px))
   }
 
+  /// Constructs a length from a numeric value and a unit.
+  pub fn new(value: CSSNumber, unit: LengthUnit) -> Length {
+    Length::Value(LengthValue::new(value, unit))
+  }
+
+  /// Parses a length that must have a unit. Unlike `Length::parse`, bare numbers
+  /// are rejected, including `0`.
+  pub fn parse_with_unit<'i>(input: &mut Parser<'i, '_>) -> Result<Length, ParseError<'i, ParserError<'i>>> {
+    let state = input.state();
+    let token = input.next()?.clone();
+    input.reset(&state);
+    if let Token::Number { .. } = token {
+      return Err(input.new_unexpected_token_error(token));
+    }
+
+    Length::parse(input)
+  }
+
+  /// Parses a length that must be a plain value, for properties where math functions are not
+  /// permitted. Math functions such as `calc()`, `min()`, `max()`, and `clamp()` are rejected with
+  /// [ParserError::MathFunctionNotAllowed](ParserError::MathFunctionNotAllowed), and other
+  /// functions as unexpected tokens.
+  pub fn parse_no_calc<'i>(input: &mut Parser<'i, '_>) -> Result<Length, ParseError<'i, ParserError<'i>>> {
+    let location = input.current_source_location();
+    let state = input.state();
+    if let Token::Function(name) = input.next()? {
+      if is_math_function(name) {
+        let name = name.clone();
+        return Err(location.new_custom_error(ParserError::MathFunctionNotAllowed(name.into())));
+      }
+    }
+    input.reset(&state);
+
+    let len = LengthValue::parse(input)?;
+    Ok(Length::Value(len))
+  }
+
+  /// Parses a length, and returns the location in the source where it starts.
+  pub fn parse_with_location<'i>(
+    input: &mut Parser<'i, '_>,
+  ) -> Result<(Length, SourceLocation), ParseError<'i, ParserError<'i>>> {
+    input.skip_whitespace();
+    let location = input.current_source_location();
+    let length = Length::parse(input)?;
+    Ok((length, location))
+  }
+
+  /// Parses one or more lengths separated by whitespace or commas, until the input is exhausted.
+  pub fn parse_all<'i>(input: &mut Parser<'i, '_>) -> Result<Vec<Length>, ParseError<'i, ParserError<'i>>> {
+    let mut lengths = Vec::new();
+    loop {
+      lengths.push(Length::parse(input)?);
+      if input.is_exhausted() {
+        return Ok(lengths);
+      }
+      let _ = input.try_parse(|input| input.expect_comma());
+    }
+  }
+
+  /// Parses a length, rejecting negative values. Only explicit values are checked,
+  /// because the sign of a `calc()` expression may not be known until it is resolved.
+  pub fn parse_non_negative<'i>(input: &mut Parser<'i, '_>) -> Result<Length, ParseError<'i, ParserError<'i>>> {
+    let location = input.current_source_location();
+    let length = Length::parse(input)?;
+    if matches!(&length, Length::Value(v) if v.to_unit_value().0 < 0.0) {
+      return Err(location.new_custom_error(ParserError::NegativeValue));
+    }
+    Ok(length)
+  }
+
   /// Attempts to convert the length to pixels.
   /// Returns `None` if the conversion is not possible.
   pub fn to_px(&self) -> Option<CSSNumber> {
@@ -666,29 +1436,297 @@ impl Length {
     }
   }
 
-  fn add(self, other: Length) -> Length {
-    let mut a = self;
-    let mut b = other;
+  /// Attempts to convert the length to pixels with double precision.
+  /// Returns `None` if the conversion is not possible.
+  pub fn to_px_f64(&self) -> Option<f64> {
+    match self {
+      Length::Value(a) => a.to_px_f64(),
+      _ => None,
+    }
+  }
 
-    if a.is_zero() {
-      return b;
+  /// Attempts to convert the length to pixels, using the given context to resolve
+  /// relative units, including within `calc()` expressions.
+  /// Returns `None` if the context does not contain the information needed to resolve the length.
+  pub fn to_px_with(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    match self {
+      Length::Value(v) => v.to_px_with(ctx),
+      Length::Calc(c) => c.resolve_with(|v| v.to_px_with(ctx)),
     }
+  }
 
-    if b.is_zero() {
-      return a;
+  /// Returns the smallest value of the length in pixels across the viewport
+  /// ranges in the given context. Returns `None` if the length cannot be resolved.
+  ///
+  /// The length is resolved at the bounds of each range, which is exact unless it uses
+  /// viewport units within non-linear functions such as `max()` or `round()`.
+  pub fn min_px(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    ctx
+      .viewport_bounds()
+      .iter()
+      .try_fold(CSSNumber::INFINITY, |min, ctx| Some(min.min(self.to_px_with(ctx)?)))
+  }
+
+  /// Returns the largest value of the length in pixels across the viewport
+  /// ranges in the given context. Returns `None` if the length cannot be resolved.
+  ///
+  /// The length is resolved at the bounds of each range, which is exact unless it uses
+  /// viewport units within non-linear functions such as `min()` or `round()`.
+  pub fn max_px(&self, ctx: &LengthContext) -> Option<CSSNumber> {
+    ctx
+      .viewport_bounds()
+      .iter()
+      .try_fold(CSSNumber::NEG_INFINITY, |max, ctx| Some(max.max(self.to_px_with(ctx)?)))
+  }
+
+  /// Returns a length with the same numeric value, but in the given unit.
+  /// Unlike a conversion, the magnitude is preserved, e.g. `10px` becomes `10pt`.
+  /// Returns `None` if the unit is unknown, or if the length is a `calc()` expression.
+  pub fn with_unit(&self, unit: &str) -> Option<Length> {
+    match self {
+      Length::Value(v) => v.with_unit(unit).map(Length::Value),
+      _ => None,
     }
+  }
 
-    if a.is_sign_negative() && b.is_sign_positive() {
-      std::mem::swap(&mut a, &mut b);
+  /// Returns the numeric value and unit string for the length.
+  /// Returns `None` if the length is a `calc()` expression.
+  pub fn to_unit_value(&self) -> Option<(CSSNumber, &str)> {
+    match self {
+      Length::Value(v) => Some(v.to_unit_value()),
+      _ => None,
     }
+  }
 
-    match (a, b) {
-      (Length::Calc(a), Length::Calc(b)) => return Length::Calc(Box::new(a.add(*b))),
-      (Length::Calc(calc), b) => {
-        if let Calc::Value(a) = *calc {
-          a.add(b)
-        } else {
-          Length::Calc(Box::new(Calc::Sum(Box::new((*calc).into()), Box::new(b.into()))))
+  /// Returns the unit shared by all of the given lengths, or `"px"` if they all have absolute
+  /// units and can therefore be converted to pixels. Returns `None` if the list is empty, or
+  /// if it contains different relative units or `calc()` expressions.
+  pub fn common_unit(lengths: &[Length]) -> Option<&'static str> {
+    let mut values = Vec::with_capacity(lengths.len());
+    for length in lengths {
+      match length {
+        Length::Value(v) => values.push(v),
+        Length::Calc(_) => return None,
+      }
+    }
+
+    let (_, unit) = values.first()?.to_unit_value();
+    if values.iter().all(|v| v.to_unit_value().1 == unit) {
+      Some(unit)
+    } else if values.iter().all(|v| v.to_px().is_some()) {
+      Some("px")
+    } else {
+      None
+    }
+  }
+
+  /// Constructs a length from a numeric value and unit string, as returned by
+  /// [to_unit_value](Length::to_unit_value). Returns `None` if the unit is unknown.
+  pub fn from_unit_value((value, unit): (CSSNumber, &str)) -> Option<Length> {
+    LengthValue::from_unit(value, unit).map(Length::Value)
+  }
+
+  /// Returns the number of bytes that [to_css](ToCss::to_css) would write with the default
+  /// printer options, without allocating the serialized string. If serialization fails, the
+  /// number of bytes written before the failure is returned.
+  pub fn serialized_len(&self) -> usize {
+    let mut counter = ByteCounter(0);
+    let mut printer = Printer::new(&mut counter, PrinterOptions::default());
+    let _ = self.to_css(&mut printer);
+    counter.0
+  }
+
+  /// Returns a length with the numeric value of each unit multiplied by the scale factor for that
+  /// unit in `factors`, including within `calc()` expressions. For example, this can be used to
+  /// scale all `rem` values by 1.25 while leaving `px` values alone. Units with no scale factor are unchanged.
+  pub fn scale_units(&self, factors: &UnitScaleMap) -> Length {
+    match self {
+      Length::Value(v) => {
+        let (value, unit) = v.to_unit_value();
+        match factors.get(unit) {
+          Some(factor) => Length::Value(v.map(|_| value * factor)),
+          None => self.clone(),
+        }
+      }
+      Length::Calc(c) => Length::Calc(Box::new(c.map_values(|v| v.scale_units(factors)))),
+    }
+  }
+
+  /// Returns a length with an absolute value rounded to the nearest integer in the same unit,
+  /// as in [LengthValue::round](LengthValue::round). Relative lengths and `calc()` expressions
+  /// are returned unchanged.
+  pub fn round(&self) -> Length {
+    match self {
+      Length::Value(v) => Length::Value(v.round()),
+      Length::Calc(_) => self.clone(),
+    }
+  }
+
+  /// Snaps an absolute length to the nearest value on the geometric scale `base * ratio^n`
+  /// for integer `n`, in pixels. For example, with a base of `16` and a ratio of `1.25`, `15px`
+  /// snaps to `16px` and `19px` to `20px`. Relative lengths, `calc()` expressions, lengths that
+  /// are not positive, and invalid scales are returned unchanged.
+  pub fn snap_to_scale(&self, base: CSSNumber, ratio: CSSNumber) -> Length {
+    let px = match self.to_px() {
+      Some(px) if px > 0.0 && base > 0.0 && ratio > 0.0 && ratio != 1.0 => px,
+      _ => return self.clone(),
+    };
+
+    let n = (px / base).ln() / ratio.ln();
+    let lower = base * ratio.powf(n.floor());
+    let upper = base * ratio.powf(n.ceil());
+    if px - lower.min(upper) <= upper.max(lower) - px {
+      Length::px(lower.min(upper))
+    } else {
+      Length::px(upper.max(lower))
+    }
+  }
+
+  /// Returns whether the length is an explicit value.
+  pub fn is_value(&self) -> bool {
+    matches!(self, Length::Value(_))
+  }
+
+  /// Returns whether the length is a `calc()` expression.
+  pub fn is_calc(&self) -> bool {
+    matches!(self, Length::Calc(_))
+  }
+
+  /// Returns whether the length is negative.
+  ///
+  /// For `calc()` expressions, the sign generally cannot be known until the expression is
+  /// resolved (e.g. `calc(1px - 1em)`), in which case `None` is returned.
+  pub fn is_negative(&self) -> Option<bool> {
+    self.try_sign().map(|sign| sign < 0.0)
+  }
+
+  /// Returns a description of the length for export to external formats, including
+  /// its value in pixels if it is absolute.
+  pub fn to_token(&self) -> LengthToken {
+    let (value, unit) = match self {
+      Length::Value(v) => {
+        let (value, unit) = v.to_unit_value();
+        (value, unit.to_owned())
+      }
+      Length::Calc(_) => (0.0, "calc".to_owned()),
+    };
+    LengthToken {
+      value,
+      unit,
+      resolvable_px: self.to_px_with(&LengthContext::default()),
+    }
+  }
+
+  /// Converts the length into a [ResolvedLength](ResolvedLength) if it is absolute,
+  /// including `calc()` expressions of only absolute units.
+  pub fn into_resolved(self) -> Option<ResolvedLength> {
+    self.to_px_with(&LengthContext::default()).map(ResolvedLength)
+  }
+
+  /// Returns whether the length can only be resolved with information about the layout,
+  /// i.e. it uses a font-relative, viewport-relative, or container-relative unit, or is a
+  /// `calc()` expression containing one.
+  pub fn needs_layout(&self) -> bool {
+    match self {
+      Length::Value(v) => v.needs_layout(),
+      Length::Calc(c) => c.any_value(|v| v.needs_layout()),
+    }
+  }
+
+  /// Removes identity operations from a `calc()` expression using [Calc::normalize](Calc::normalize),
+  /// and unwraps it if only a single length remains.
+  pub fn normalize(self) -> Length {
+    match self {
+      Length::Calc(c) => match c.normalize() {
+        Calc::Value(v) => *v,
+        c => Length::Calc(Box::new(c)),
+      },
+      length => length,
+    }
+  }
+
+  /// Compares two lengths, ignoring `calc()` wrappers around a single value. For example,
+  /// `5px` is equal to both `Calc::Value(5px)` and `calc(5px)`.
+  pub fn eq_unwrapped(&self, other: &Length) -> bool {
+    fn unwrap(length: &Length) -> &Length {
+      match length {
+        Length::Calc(c) => match &**c {
+          Calc::Value(v) => unwrap(v),
+          Calc::Function(f) => match &**f {
+            MathFunction::Calc(Calc::Value(v)) => unwrap(v),
+            _ => length,
+          },
+          _ => length,
+        },
+        _ => length,
+      }
+    }
+
+    unwrap(self) == unwrap(other)
+  }
+
+  /// Converts `em` values to the equivalent `rem` values, given the size of `1em` and `1rem` in pixels.
+  /// Other units are unchanged, and `calc()` expressions are converted recursively.
+  pub fn em_to_rem(&self, em_px: CSSNumber, rem_px: CSSNumber) -> Length {
+    match self {
+      Length::Value(LengthValue::Em(value)) => Length::Value(LengthValue::Rem(value * em_px / rem_px)),
+      Length::Value(v) => Length::Value(v.clone()),
+      Length::Calc(c) => Length::Calc(Box::new(c.map_values(move |v| v.em_to_rem(em_px, rem_px)))),
+    }
+  }
+
+  /// Returns whether the length uses a viewport-relative unit, including within a `calc()` expression.
+  pub fn uses_viewport_units(&self) -> bool {
+    match self {
+      Length::Value(v) => v.is_viewport_relative(),
+      Length::Calc(c) => c.any_value(|v| v.uses_viewport_units()),
+    }
+  }
+
+  /// Attempts to add another length to this one without producing a `calc()` expression.
+  /// Returns the original lengths if they cannot be combined into a single value,
+  /// e.g. when they have incompatible units.
+  pub fn try_add(self, other: Length) -> Result<Length, (Length, Length)> {
+    match TryAdd::try_add(&self, &other) {
+      Some(res) => Ok(res),
+      None => Err((self, other)),
+    }
+  }
+
+  /// Returns whether another length can be added to this one without producing a `calc()`
+  /// expression, i.e. whether [try_add](Length::try_add) would succeed.
+  pub fn can_fold_add(&self, other: &Length) -> bool {
+    match (self, other) {
+      (Length::Value(a), Length::Value(b)) => {
+        std::mem::discriminant(a) == std::mem::discriminant(b) || (a.to_px().is_some() && b.to_px().is_some())
+      }
+      _ => TryAdd::try_add(self, other).is_some(),
+    }
+  }
+
+  fn add(self, other: Length) -> Length {
+    let mut a = self;
+    let mut b = other;
+
+    if a.is_zero() {
+      return b;
+    }
+
+    if b.is_zero() {
+      return a;
+    }
+
+    if a.is_sign_negative() && b.is_sign_positive() {
+      std::mem::swap(&mut a, &mut b);
+    }
+
+    match (a, b) {
+      (Length::Calc(a), Length::Calc(b)) => return Length::Calc(Box::new(a.add(*b))),
+      (Length::Calc(calc), b) => {
+        if let Calc::Value(a) = *calc {
+          a.add(b)
+        } else {
+          Length::Calc(Box::new(Calc::Sum(Box::new((*calc).into()), Box::new(b.into()))))
         }
       }
       (a, Length::Calc(calc)) => {
@@ -703,6 +1741,219 @@ impl Length {
   }
 }
 
+/// A map of scale factors for length units, used by [Length::scale_units](Length::scale_units).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitScaleMap {
+  factors: Vec<(String, CSSNumber)>,
+}
+
+impl UnitScaleMap {
+  /// Creates an empty scale map.
+  pub fn new() -> UnitScaleMap {
+    UnitScaleMap::default()
+  }
+
+  /// Sets the scale factor for the given unit, replacing any existing factor. Units are case-insensitive.
+  pub fn with(mut self, unit: &str, factor: CSSNumber) -> UnitScaleMap {
+    let unit = unit.to_ascii_lowercase();
+    match self.factors.iter_mut().find(|(u, _)| *u == unit) {
+      Some(entry) => entry.1 = factor,
+      None => self.factors.push((unit, factor)),
+    }
+    self
+  }
+
+  /// Returns the scale factor for the given unit, if any.
+  pub fn get(&self, unit: &str) -> Option<CSSNumber> {
+    self
+      .factors
+      .iter()
+      .find(|(u, _)| u.eq_ignore_ascii_case(unit))
+      .map(|(_, factor)| *factor)
+  }
+}
+
+/// A description of a length for export to external formats such as design tokens,
+/// returned by [Length::to_token](Length::to_token).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(rename_all = "camelCase")
+)]
+pub struct LengthToken {
+  /// The value of the length in its unit. Always zero for `calc()` expressions.
+  pub value: CSSNumber,
+  /// The unit of the length, or `"calc"` for `calc()` expressions.
+  pub unit: String,
+  /// The length in pixels, if it can be resolved without any context.
+  pub resolvable_px: Option<CSSNumber>,
+}
+
+/// A length that is known to be absolute, returned by [Length::into_resolved](Length::into_resolved).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedLength(CSSNumber);
+
+impl ResolvedLength {
+  /// Returns the length in pixels.
+  pub fn to_px(&self) -> CSSNumber {
+    self.0
+  }
+}
+
+impl From<ResolvedLength> for Length {
+  fn from(length: ResolvedLength) -> Length {
+    Length::px(length.0)
+  }
+}
+
+/// A wrapper that orders lengths by their value in pixels, so that a list of lengths can be
+/// sorted, e.g. `1in`, `10px`, and `50pt` sort as `10px`, `50pt`, `1in`.
+///
+/// Lengths that cannot be converted to pixels, such as relative lengths and most `calc()`
+/// expressions, sort after all absolute lengths and are considered equal to each other.
+#[derive(Debug, Clone)]
+pub struct ByPx(pub Length);
+
+impl PartialEq for ByPx {
+  fn eq(&self, other: &ByPx) -> bool {
+    self.cmp(other) == std::cmp::Ordering::Equal
+  }
+}
+
+impl Eq for ByPx {}
+
+impl PartialOrd for ByPx {
+  fn partial_cmp(&self, other: &ByPx) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for ByPx {
+  fn cmp(&self, other: &ByPx) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    match (self.0.to_px(), other.0.to_px()) {
+      (Some(a), Some(b)) => a.total_cmp(&b),
+      (Some(_), None) => Ordering::Less,
+      (None, Some(_)) => Ordering::Greater,
+      (None, None) => Ordering::Equal,
+    }
+  }
+}
+
+/// A cache that deduplicates equal lengths, returning a shared [Rc](std::rc::Rc) for each.
+/// This can reduce allocations when transforming large stylesheets with many repeated values.
+#[derive(Debug, Default)]
+pub struct LengthInterner {
+  values: std::collections::HashMap<(&'static str, u32), std::rc::Rc<Length>>,
+  calcs: std::collections::HashMap<String, Vec<std::rc::Rc<Length>>>,
+}
+
+impl LengthInterner {
+  /// Creates an empty interner.
+  pub fn new() -> LengthInterner {
+    LengthInterner::default()
+  }
+
+  /// Returns a shared length equal to the given one, reusing a previously interned value if possible.
+  pub fn intern(&mut self, length: Length) -> std::rc::Rc<Length> {
+    match &length {
+      Length::Value(v) => {
+        let (value, unit) = v.to_unit_value();
+        // Positive and negative zero are equal, but have different bits.
+        let bits = if value == 0.0 { 0 } else { value.to_bits() };
+        self
+          .values
+          .entry((unit, bits))
+          .or_insert_with(|| std::rc::Rc::new(length))
+          .clone()
+      }
+      Length::Calc(_) => {
+        // calc() expressions are not hashable, so they are grouped by their serialization.
+        // This rounds numbers, so equal expressions are then found within the group.
+        let key = match length.to_css_string(PrinterOptions::default()) {
+          Ok(key) => key,
+          Err(_) => return std::rc::Rc::new(length),
+        };
+        let calcs = self.calcs.entry(key).or_default();
+        if let Some(existing) = calcs.iter().find(|c| ***c == length) {
+          return existing.clone();
+        }
+        let length = std::rc::Rc::new(length);
+        calcs.push(length.clone());
+        length
+      }
+    }
+  }
+
+  /// Returns the number of distinct lengths that have been interned.
+  pub fn len(&self) -> usize {
+    self.values.len() + self.calcs.values().map(|calcs| calcs.len()).sum::<usize>()
+  }
+
+  /// Returns whether no lengths have been interned.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/// A kind of length unit, returned by [LengthValue::unit_kind](LengthValue::unit_kind).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthUnitKind {
+  /// An absolute unit, e.g. `px` or `in`.
+  Absolute,
+  /// A font-relative unit, e.g. `em` or `rem`.
+  FontRelative,
+  /// A viewport-relative unit, e.g. `vw` or `dvh`.
+  ViewportRelative,
+  /// A container-relative unit, e.g. `cqw`.
+  ContainerRelative,
+}
+
+/// Statistics about the lengths used in a stylesheet, collected with [LengthStats::record](LengthStats::record).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LengthStats {
+  /// The number of lengths with an absolute unit.
+  pub absolute: usize,
+  /// The number of lengths with a font-relative unit.
+  pub font_relative: usize,
+  /// The number of lengths with a viewport-relative unit.
+  pub viewport_relative: usize,
+  /// The number of lengths with a container-relative unit.
+  pub container_relative: usize,
+  /// The number of `calc()` expressions.
+  pub calc: usize,
+  /// The smallest absolute length recorded, in pixels.
+  pub min_px: Option<CSSNumber>,
+  /// The largest absolute length recorded, in pixels.
+  pub max_px: Option<CSSNumber>,
+}
+
+impl LengthStats {
+  /// Records a length. `calc()` expressions are counted as a whole rather than by the units they contain.
+  pub fn record(&mut self, length: &Length) {
+    let value = match length {
+      Length::Value(v) => v,
+      Length::Calc(_) => {
+        self.calc += 1;
+        return;
+      }
+    };
+
+    match value.unit_kind() {
+      LengthUnitKind::Absolute => self.absolute += 1,
+      LengthUnitKind::FontRelative => self.font_relative += 1,
+      LengthUnitKind::ViewportRelative => self.viewport_relative += 1,
+      LengthUnitKind::ContainerRelative => self.container_relative += 1,
+    }
+
+    if let Some(px) = value.to_px() {
+      self.min_px = Some(self.min_px.map_or(px, |min| min.min(px)));
+      self.max_px = Some(self.max_px.map_or(px, |max| max.max(px)));
+    }
+  }
+}
+
 impl IsCompatible for Length {
   fn is_compatible(&self, browsers: Browsers) -> bool {
     match self {
@@ -736,13 +1987,13 @@ impl TryAdd<Length> for Length {
         }
       }
       (Length::Calc(a), other) => match &**a {
-        Calc::Value(v) => v.try_add(other),
+        Calc::Value(v) => TryAdd::try_add(&**v, other),
         Calc::Sum(a, b) => {
-          if let Some(res) = Length::Calc(Box::new(*a.clone())).try_add(other) {
+          if let Some(res) = TryAdd::try_add(&Length::Calc(Box::new(*a.clone())), other) {
             return Some(res.add(Length::from(*b.clone())));
           }
 
-          if let Some(res) = Length::Calc(Box::new(*b.clone())).try_add(other) {
+          if let Some(res) = TryAdd::try_add(&Length::Calc(Box::new(*b.clone())), other) {
             return Some(Length::from(*a.clone()).add(res));
           }
 
@@ -751,13 +2002,13 @@ impl TryAdd<Length> for Length {
         _ => None,
       },
       (other, Length::Calc(b)) => match &**b {
-        Calc::Value(v) => other.try_add(&*v),
+        Calc::Value(v) => TryAdd::try_add(other, &**v),
         Calc::Sum(a, b) => {
-          if let Some(res) = other.try_add(&Length::Calc(Box::new(*a.clone()))) {
+          if let Some(res) = TryAdd::try_add(other, &Length::Calc(Box::new(*a.clone()))) {
             return Some(res.add(Length::from(*b.clone())));
           }
 
-          if let Some(res) = other.try_add(&Length::Calc(Box::new(*b.clone()))) {
+          if let Some(res) = TryAdd::try_add(other, &Length::Calc(Box::new(*b.clone()))) {
             return Some(Length::from(*a.clone()).add(res));
           }
 
@@ -784,6 +2035,68 @@ impl std::convert::From<Calc<Length>> for Length {
   }
 }
 
+impl Calc<Length> {
+  /// Simplifies a sum in the same way as [collect_terms](Calc::collect_terms), and records a note
+  /// for each addition where the result has a different unit than both inputs, as in
+  /// [LengthValue::try_add_with_notes](LengthValue::try_add_with_notes).
+  pub fn collect_terms_with_notes(self, notes: &mut Vec<UnitConversionNote>) -> Calc<Length> {
+    self.collect_terms_with(&mut |a: &Length, b: &Length| match (a, b) {
+      (Length::Value(a), Length::Value(b)) => a.try_add_with_notes(b, notes).map(Length::Value),
+      _ => TryAdd::try_add(a, b),
+    })
+  }
+
+  /// Returns the term of a sum with the largest magnitude in pixels, considering only absolute lengths.
+  /// Returns `None` if the expression has no absolute length terms.
+  pub fn dominant_term(&self) -> Option<&Length> {
+    match self {
+      Calc::Value(v) => v.to_px().map(|_| &**v),
+      Calc::Sum(a, b) => match (a.dominant_term(), b.dominant_term()) {
+        (Some(a), Some(b)) => {
+          if b.to_px().unwrap().abs() > a.to_px().unwrap().abs() {
+            Some(b)
+          } else {
+            Some(a)
+          }
+        }
+        (a, b) => a.or(b),
+      },
+      Calc::Function(f) => match &**f {
+        MathFunction::Calc(c) => c.dominant_term(),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// Returns the coefficient and unit if the whole expression is a multiple of a single unit,
+  /// e.g. `(5.0, "em")` for `calc(2em + 3em)`. Unlike folding, this also looks through nested
+  /// sums and products. Returns `None` if the expression contains more than one unit, a number,
+  /// or a math function other than `calc()`.
+  pub fn as_single_unit_multiple(&self) -> Option<(CSSNumber, &str)> {
+    match self {
+      Calc::Value(v) => match &**v {
+        Length::Value(v) => Some(v.to_unit_value()),
+        Length::Calc(c) => c.as_single_unit_multiple(),
+      },
+      Calc::Sum(a, b) => {
+        let (a, unit) = a.as_single_unit_multiple()?;
+        let (b, other) = b.as_single_unit_multiple()?;
+        if unit != other {
+          return None;
+        }
+        Some((a + b, unit))
+      }
+      Calc::Product(num, c) => c.as_single_unit_multiple().map(|(value, unit)| (num * value, unit)),
+      Calc::Function(f) => match &**f {
+        MathFunction::Calc(c) => c.as_single_unit_multiple(),
+        _ => None,
+      },
+      Calc::Number(_) => None,
+    }
+  }
+}
+
 impl std::cmp::PartialOrd<Length> for Length {
   fn partial_cmp(&self, other: &Length) -> Option<std::cmp::Ordering> {
     match (self, other) {
@@ -829,6 +2142,16 @@ impl TrySign for Length {
 
 impl_try_from_angle!(Length);
 
+/// A `Write` sink that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl std::fmt::Write for ByteCounter {
+  fn write_str(&mut self, s: &str) -> std::fmt::Result {
+    self.0 += s.len();
+    Ok(())
+  }
+}
+
 /// Either a [`<length>`](https://www.w3.org/TR/css-values-4/#lengths) or a [`<number>`](https://www.w3.org/TR/css-values-4/#numbers).
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "visitor", derive(Visit))]
@@ -900,3 +2223,1590 @@ impl IsCompatible for LengthOrNumber {
     }
   }
 }
+
+/// A [`<length>`](https://www.w3.org/TR/css-values-4/#lengths), or a dimension with an unknown unit.
+///
+/// This is an opt-in alternative to [Length](Length) for callers that want to preserve
+/// dimensions in units that are not recognized yet. Parsing a plain [Length](Length)
+/// still rejects unknown units.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "visitor", derive(Visit))]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(tag = "type", rename_all = "kebab-case")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "into_owned", derive(static_self::IntoOwned))]
+pub enum LengthOrUnknown<'i> {
+  /// A length.
+  Length {
+    /// The length value.
+    value: Length,
+  },
+  /// A dimension with an unknown unit, which is preserved as is.
+  #[cfg_attr(feature = "visitor", skip_type)]
+  Unknown {
+    /// The numeric value.
+    value: CSSNumber,
+    /// The unit.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    unit: CowArcStr<'i>,
+  },
+}
+
+impl<'i> Parse<'i> for LengthOrUnknown<'i> {
+  fn parse<'t>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i, ParserError<'i>>> {
+    if let Ok(length) = input.try_parse(Length::parse) {
+      return Ok(LengthOrUnknown::Length { value: length });
+    }
+
+    let location = input.current_source_location();
+    match *input.next()? {
+      // Values that overflow f32 (e.g. `1e39foo`) are rejected, as for known units.
+      Token::Dimension { value, .. } if !value.is_finite() => {
+        Err(location.new_custom_error(ParserError::InvalidValue))
+      }
+      Token::Dimension { value, ref unit, .. } => Ok(LengthOrUnknown::Unknown {
+        value,
+        unit: unit.into(),
+      }),
+      ref t => Err(location.new_unexpected_token_error(t.clone())),
+    }
+  }
+}
+
+impl<'i> ToCss for LengthOrUnknown<'i> {
+  fn to_css<W>(&self, dest: &mut Printer<W>) -> Result<(), PrinterError>
+  where
+    W: std::fmt::Write,
+  {
+    match self {
+      LengthOrUnknown::Length { value } => value.to_css(dest),
+      LengthOrUnknown::Unknown { value, unit } => serialize_dimension(*value, unit, dest),
+    }
+  }
+}
+
+/// A pair of lengths along the [block](https://www.w3.org/TR/css-writing-modes-4/#block-axis) and
+/// [inline](https://www.w3.org/TR/css-writing-modes-4/#inline-axis) axes, which can be mapped to
+/// physical axes for a given writing mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicalLength {
+  /// The length along the block axis.
+  pub block: Length,
+  /// The length along the inline axis.
+  pub inline: Length,
+}
+
+impl LogicalLength {
+  /// Creates a logical length from a block and inline length.
+  pub fn new(block: Length, inline: Length) -> LogicalLength {
+    LogicalLength { block, inline }
+  }
+
+  /// Maps the logical lengths to physical axes for the given writing mode, returning the
+  /// horizontal length (applied to the left and right sides) and the vertical length (applied
+  /// to the top and bottom sides). In horizontal writing modes the inline axis is horizontal,
+  /// and in vertical writing modes it is vertical.
+  pub fn resolve(&self, writing_mode: WritingMode) -> (Length, Length) {
+    if writing_mode.is_vertical() {
+      (self.block.clone(), self.inline.clone())
+    } else {
+      (self.inline.clone(), self.block.clone())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse<'i, T>(
+    source: &'i str,
+    f: fn(&mut Parser<'i, '_>) -> Result<T, ParseError<'i, ParserError<'i>>>,
+  ) -> Result<T, ParseErrorKind<'i, ParserError<'i>>> {
+    let mut input = ParserInput::new(source);
+    let mut parser = Parser::new(&mut input);
+    parser.parse_entirely(f).map_err(|e| e.kind)
+  }
+
+  #[test]
+  fn test_with_unit() {
+    assert_eq!(
+      Length::px(10.0).with_unit("pt"),
+      Some(Length::Value(LengthValue::Pt(10.0)))
+    );
+    assert_eq!(
+      Length::px(10.0).with_unit("REM"),
+      Some(Length::Value(LengthValue::Rem(10.0)))
+    );
+    assert_eq!(Length::px(10.0).with_unit("foo"), None);
+    let calc = Length::parse_string("calc(1px + 1em)").unwrap();
+    assert_eq!(calc.with_unit("pt"), None);
+  }
+
+  #[test]
+  fn test_leading_and_trailing_dot() {
+    // A leading dot is valid CSS number syntax.
+    let length = Length::parse_string(".5px").unwrap();
+    assert_eq!(length, Length::px(0.5));
+    assert_eq!(length.to_css_string(PrinterOptions::default()).unwrap(), ".5px");
+    assert_eq!(
+      Length::parse_string("-.5em").unwrap(),
+      Length::Value(LengthValue::Em(-0.5))
+    );
+
+    // A trailing dot is not part of the number, so `5.px` tokenizes as
+    // `5` followed by a `.` delimiter and the `px` ident, and is invalid.
+    assert!(Length::parse_string("5.px").is_err());
+    assert!(Length::parse_string("5.").is_err());
+  }
+
+  #[test]
+  fn test_zero_in_calc() {
+    fn to_css(length: &LengthPercentage) -> String {
+      length.to_css_string(PrinterOptions::default()).unwrap()
+    }
+
+    // Outside calc(), the unit of a zero length can be omitted.
+    assert_eq!(to_css(&LengthPercentage::px(0.0)), "0");
+
+    // Inside calc(), a unitless zero would be parsed as a <number>, so the unit must be kept.
+    let sum = Calc::Sum(
+      Box::new(Calc::Value(Box::new(LengthPercentage::px(0.0)))),
+      Box::new(Calc::Value(Box::new(LengthPercentage::Percentage(Percentage(0.5))))),
+    );
+    let calc = LengthPercentage::Calc(Box::new(Calc::Function(Box::new(MathFunction::Calc(sum)))));
+    assert_eq!(to_css(&calc), "calc(0px + 50%)");
+
+    let min = LengthPercentage::parse_string("min(0px, 50%)").unwrap();
+    assert_eq!(to_css(&min), "min(0px, 50%)");
+    let max = LengthPercentage::parse_string("max(0em, 50%)").unwrap();
+    assert_eq!(to_css(&max), "max(0em, 50%)");
+
+    // Relative units are preserved in the same way.
+    let sum = Calc::Sum(
+      Box::new(Calc::Value(Box::new(LengthPercentage::Dimension(LengthValue::Em(0.0))))),
+      Box::new(Calc::Value(Box::new(LengthPercentage::Dimension(LengthValue::Vw(
+        10.0,
+      ))))),
+    );
+    let calc = LengthPercentage::Calc(Box::new(Calc::Function(Box::new(MathFunction::Calc(sum)))));
+    assert_eq!(to_css(&calc), "calc(0em + 10vw)");
+    assert_eq!(to_css(&LengthPercentage::Dimension(LengthValue::Em(0.0))), "0");
+
+    // A zero term in a sum is dropped when parsing, so the unit doesn't matter.
+    assert_eq!(
+      LengthPercentage::parse_string("calc(0em + 50%)").unwrap(),
+      LengthPercentage::Percentage(Percentage(0.5))
+    );
+    assert_eq!(
+      LengthPercentage::parse_string("calc(0.0em + 1em)").unwrap(),
+      LengthPercentage::Dimension(LengthValue::Em(1.0))
+    );
+  }
+
+  #[test]
+  fn test_to_px_with() {
+    let ctx = LengthContext {
+      font_size: Some(16.0),
+      root_font_size: Some(10.0),
+      viewport_width: Some(1000.0),
+      viewport_height: Some(500.0),
+      ..LengthContext::default()
+    };
+    let resolve = |s: &str| LengthPercentage::parse_string(s).unwrap().to_px_with(&ctx, 200.0);
+    assert_eq!(resolve("calc(50% + 1em)"), Some(116.0));
+    assert_eq!(resolve("1in"), Some(96.0));
+    assert_eq!(resolve("25%"), Some(50.0));
+    assert_eq!(resolve("2rem"), Some(20.0));
+    assert_eq!(resolve("10vmin"), Some(50.0));
+    assert_eq!(resolve("max(10vw, 50%)"), Some(100.0));
+    assert_eq!(resolve("calc(100% - 2 * 1em)"), Some(168.0));
+    assert_eq!(resolve("1cqw"), None);
+    assert_eq!(
+      LengthPercentage::parse_string("1em")
+        .unwrap()
+        .to_px_with(&LengthContext::default(), 200.0),
+      None
+    );
+
+    let length = Length::parse_string("calc(1em + 10px)").unwrap();
+    assert_eq!(length.to_px_with(&ctx), Some(26.0));
+  }
+
+  #[test]
+  fn test_resolve() {
+    use LengthValue::*;
+    let ctx = LengthContext {
+      font_size: Some(16.0),
+      root_font_size: Some(10.0),
+      x_height: Some(8.0),
+      ch_width: Some(9.0),
+      viewport_width: Some(1000.0),
+      viewport_height: Some(500.0),
+      ..LengthContext::default()
+    };
+    assert_eq!(Em(2.0).resolve_relative(&ctx), Some(32.0));
+    assert_eq!(Rem(2.0).resolve_relative(&ctx), Some(20.0));
+    assert_eq!(Ex(2.0).resolve_relative(&ctx), Some(16.0));
+    assert_eq!(Ch(2.0).resolve_relative(&ctx), Some(18.0));
+    assert_eq!(Vw(10.0).resolve_relative(&ctx), Some(100.0));
+    assert_eq!(Vh(10.0).resolve_relative(&ctx), Some(50.0));
+    assert_eq!(Vmin(10.0).resolve_relative(&ctx), Some(50.0));
+    assert_eq!(Vmax(10.0).resolve_relative(&ctx), Some(100.0));
+    assert_eq!(Cqw(10.0).resolve_relative(&ctx), None);
+    assert_eq!(Px(10.0).resolve_relative(&ctx), None);
+    assert_eq!(Ex(1.0).resolve_relative(&LengthContext::default()), None);
+    assert_eq!(Ch(1.0).to_px_with(&ctx), Some(9.0));
+  }
+
+  #[test]
+  fn test_collect_terms() {
+    let value = |v: LengthValue| Box::new(Calc::Value(Box::new(Length::Value(v))));
+    // 1em + (2px + 3em), where the two em terms are not adjacent.
+    let sum = Calc::Sum(
+      value(LengthValue::Em(1.0)),
+      Box::new(Calc::Sum(value(LengthValue::Px(2.0)), value(LengthValue::Em(3.0)))),
+    );
+    let calc = Length::Calc(Box::new(Calc::Function(Box::new(MathFunction::Calc(
+      sum.collect_terms(),
+    )))));
+    assert_eq!(
+      calc.to_css_string(PrinterOptions::default()).unwrap(),
+      "calc(4em + 2px)"
+    );
+
+    let sum = Calc::Sum(
+      Box::new(Calc::Sum(value(LengthValue::In(1.0)), value(LengthValue::Vw(2.0)))),
+      value(LengthValue::Px(4.0)),
+    );
+    assert_eq!(
+      sum.collect_terms(),
+      Calc::Sum(value(LengthValue::Px(100.0)), value(LengthValue::Vw(2.0)))
+    );
+  }
+
+  #[test]
+  fn test_normalize() {
+    let value = |v: LengthValue| Box::new(Calc::Value(Box::new(Length::Value(v))));
+    let calc = |c: Calc<Length>| Calc::Function(Box::new(MathFunction::Calc(c)));
+
+    let product = calc(Calc::Product(1.0, value(LengthValue::Px(5.0))));
+    assert_eq!(product.normalize(), *value(LengthValue::Px(5.0)));
+
+    let sum = calc(Calc::Sum(value(LengthValue::Px(5.0)), value(LengthValue::Px(0.0))));
+    assert_eq!(sum.normalize(), *value(LengthValue::Px(5.0)));
+
+    let sum = calc(Calc::Sum(
+      value(LengthValue::Em(0.0)),
+      Box::new(Calc::Product(1.0, value(LengthValue::Vw(2.0)))),
+    ));
+    assert_eq!(sum.normalize(), *value(LengthValue::Vw(2.0)));
+
+    let sum = calc(Calc::Sum(
+      value(LengthValue::Em(1.0)),
+      Box::new(Calc::Product(2.0, value(LengthValue::Px(3.0)))),
+    ));
+    assert_eq!(sum.clone().normalize(), sum);
+  }
+
+  #[test]
+  fn test_normalize_length() {
+    let to_css = |length: &Length| length.to_css_string(PrinterOptions::default()).unwrap();
+    let length = Length::Calc(Box::new(Calc::Value(Box::new(Length::px(5.0)))));
+    assert_eq!(to_css(&length), "5px");
+    assert_eq!(length.normalize(), Length::px(5.0));
+
+    let length = Length::Calc(Box::new(Calc::Function(Box::new(MathFunction::Calc(Calc::Value(
+      Box::new(Length::px(5.0)),
+    ))))));
+    assert_eq!(to_css(&length), "calc(5px)");
+    assert_eq!(length.normalize(), Length::px(5.0));
+
+    let length = Length::parse_string("calc(1em + 5px)").unwrap();
+    assert_eq!(length.clone().normalize(), length);
+  }
+
+  #[test]
+  fn test_as_single_unit_multiple() {
+    let value = |v: LengthValue| Box::new(Calc::Value(Box::new(Length::Value(v))));
+    let sum = Calc::Sum(value(LengthValue::Em(2.0)), value(LengthValue::Em(3.0)));
+    assert_eq!(sum.as_single_unit_multiple(), Some((5.0, "em")));
+
+    let calc = Calc::Function(Box::new(MathFunction::Calc(Calc::Sum(
+      value(LengthValue::Em(1.0)),
+      Box::new(Calc::Product(2.0, Box::new(sum))),
+    ))));
+    assert_eq!(calc.as_single_unit_multiple(), Some((11.0, "em")));
+
+    let mixed = Calc::Sum(value(LengthValue::Em(2.0)), value(LengthValue::Rem(3.0)));
+    assert_eq!(mixed.as_single_unit_multiple(), None);
+
+    let min = match Length::parse_string("min(1em, 2rem)").unwrap() {
+      Length::Calc(c) => *c,
+      _ => unreachable!(),
+    };
+    assert_eq!(min.as_single_unit_multiple(), None);
+  }
+
+  #[test]
+  fn test_from_unit_value() {
+    for s in ["2em", "10px", "-1.5rem", "3dvmin", "0cqw"] {
+      let length = Length::parse_string(s).unwrap();
+      assert_eq!(Length::from_unit_value(length.to_unit_value().unwrap()), Some(length));
+    }
+    assert_eq!(
+      Length::from_unit_value((1.0, "PT")),
+      Some(Length::Value(LengthValue::Pt(1.0)))
+    );
+    assert_eq!(Length::from_unit_value((1.0, "foo")), None);
+    assert_eq!(Length::parse_string("calc(1px + 1em)").unwrap().to_unit_value(), None);
+  }
+
+  #[test]
+  fn test_common_unit() {
+    let common_unit = |lengths: &[&str]| {
+      let lengths: Vec<Length> = lengths.iter().map(|s| Length::parse_string(s).unwrap()).collect();
+      Length::common_unit(&lengths)
+    };
+    assert_eq!(common_unit(&["1em", "2em"]), Some("em"));
+    assert_eq!(common_unit(&["1px", "1in"]), Some("px"));
+    assert_eq!(common_unit(&["1in", "2in"]), Some("in"));
+    assert_eq!(common_unit(&["1em", "2rem"]), None);
+    assert_eq!(common_unit(&["1em", "2px"]), None);
+    assert_eq!(common_unit(&["1em", "calc(1em + 1px)"]), None);
+    assert_eq!(common_unit(&[]), None);
+  }
+
+  #[test]
+  fn test_eq_unwrapped() {
+    let value = Length::Calc(Box::new(Calc::Value(Box::new(Length::px(5.0)))));
+    assert_ne!(Length::px(5.0), value);
+    assert!(Length::px(5.0).eq_unwrapped(&value));
+    assert!(value.eq_unwrapped(&Length::px(5.0)));
+
+    let function = Length::Calc(Box::new(Calc::Function(Box::new(MathFunction::Calc(Calc::Value(
+      Box::new(value),
+    ))))));
+    assert!(function.eq_unwrapped(&Length::px(5.0)));
+    assert!(!function.eq_unwrapped(&Length::px(6.0)));
+
+    let sum = Length::parse_string("calc(1em + 5px)").unwrap();
+    assert!(sum.eq_unwrapped(&sum.clone()));
+    assert!(!sum.eq_unwrapped(&Length::px(5.0)));
+  }
+
+  #[test]
+  fn test_length_or_number_calc() {
+    assert_eq!(
+      LengthOrNumber::parse_string("calc(2 * 3)").unwrap(),
+      LengthOrNumber::Number(6.0)
+    );
+    assert_eq!(
+      LengthOrNumber::parse_string("calc(1 + 2)").unwrap(),
+      LengthOrNumber::Number(3.0)
+    );
+    assert_eq!(
+      LengthOrNumber::parse_string("calc(2 * 3px)").unwrap(),
+      LengthOrNumber::Length(Length::px(6.0))
+    );
+  }
+
+  #[test]
+  fn test_minify_absolute() {
+    use LengthValue::*;
+    assert_eq!(Pt(12.0).minify_absolute(), Pc(1.0));
+    assert_eq!(Pt(24.0).minify_absolute(), Pc(2.0));
+    assert_eq!(Px(16.0).minify_absolute(), Pc(1.0));
+    assert_eq!(Px(96.0).minify_absolute(), In(1.0));
+    assert_eq!(Px(9.6).minify_absolute(), In(0.1));
+    assert_eq!(In(0.1).minify_absolute(), In(0.1));
+    assert_eq!(In(0.5).minify_absolute(), Pc(3.0));
+    assert_eq!(Px(48.0).minify_absolute(), Pc(3.0));
+    // Ties keep the original unit, e.g. `24px` is the same length as `18pt`.
+    assert_eq!(Px(24.0).minify_absolute(), Px(24.0));
+    assert_eq!(In(2.0).minify_absolute(), In(2.0));
+    // Fractional values in larger units are considered too, in whichever direction is shorter.
+    assert_eq!(In(0.25).minify_absolute(), Px(24.0));
+    assert_eq!(Px(19.2).minify_absolute(), In(0.2));
+    assert_eq!(Px(72.0).minify_absolute(), Px(72.0));
+    assert_eq!(Px(1.2).minify_absolute(), Pt(0.9));
+    // Lossy conversions are never performed.
+    assert_eq!(Cm(1.0).minify_absolute(), Cm(1.0));
+    assert_eq!(Px(1.0).minify_absolute(), Px(1.0));
+    assert_eq!(Em(16.0).minify_absolute(), Em(16.0));
+    assert_eq!(Px(0.0).minify_absolute(), Px(0.0));
+    // Values that would not be printed exactly are left unchanged.
+    assert_eq!(Px(1e30).minify_absolute(), Px(1e30));
+    assert_eq!(Px(1.23456).minify_absolute(), Px(1.23456));
+    assert_eq!(Px(f32::INFINITY).minify_absolute(), Px(f32::INFINITY));
+    assert!(matches!(Px(f32::NAN).minify_absolute(), Px(value) if value.is_nan()));
+  }
+
+  #[test]
+  fn test_non_finite() {
+    // These overflow f32 when tokenized.
+    assert!(LengthValue::parse_string("1e39px").is_err());
+    assert!(LengthValue::parse_string("-1e39em").is_err());
+    assert!(LengthValue::parse_string("1e39").is_err());
+    assert!(Length::parse_string("calc(1e39px + 1em)").is_err());
+    assert!(LengthPercentage::parse_string("1e39px").is_err());
+
+    let mut input = ParserInput::new("1e39px");
+    let mut parser = Parser::new(&mut input);
+    assert_eq!(LengthValue::try_from(parser.next().unwrap()), Err(()));
+
+    assert_eq!(LengthValue::parse_string("1e38px").unwrap(), LengthValue::Px(1e38));
+  }
+
+  #[test]
+  fn test_resolve_length() {
+    fn resolve<'i, T: Parse<'i> + ResolveLength>(s: &'i str, ctx: &LengthContext) -> Option<CSSNumber> {
+      T::parse_string(s).unwrap().resolve(ctx)
+    }
+
+    let ctx = LengthContext {
+      font_size: Some(16.0),
+      percentage_basis: Some(200.0),
+      ..LengthContext::default()
+    };
+    assert_eq!(resolve::<Length>("calc(2em + 1in)", &ctx), Some(128.0));
+    assert_eq!(resolve::<Length>("1rem", &ctx), None);
+    assert_eq!(resolve::<LengthPercentage>("calc(50% + 1em)", &ctx), Some(116.0));
+    assert_eq!(resolve::<LengthPercentage>("10%", &LengthContext::default()), None);
+    assert_eq!(resolve::<LengthPercentageOrAuto>("25%", &ctx), Some(50.0));
+    assert_eq!(resolve::<LengthPercentageOrAuto>("auto", &ctx), None);
+  }
+
+  #[test]
+  fn test_calc_product_types() {
+    // A product must have at least one <number> operand.
+    assert!(Length::parse_string("calc(10px * 20px)").is_err());
+    assert!(Length::parse_string("calc(10px * (1px + 2em))").is_err());
+    assert!(Length::parse_string("calc(10px / 2px)").is_err());
+    assert!(LengthPercentage::parse_string("calc(10% * 20px)").is_err());
+    assert_eq!(Length::parse_string("calc(10px * 2)").unwrap(), Length::px(20.0));
+    assert_eq!(Length::parse_string("calc(2 * 10px)").unwrap(), Length::px(20.0));
+  }
+
+  #[test]
+  fn test_into_length_percentage() {
+    fn to_css<T: Into<LengthPercentage>>(value: T) -> String {
+      value.into().to_css_string(PrinterOptions::default()).unwrap()
+    }
+
+    assert_eq!(LengthPercentage::from(Length::px(10.0)), LengthPercentage::px(10.0));
+    assert_eq!(
+      LengthPercentage::from(Percentage(0.5)),
+      LengthPercentage::Percentage(Percentage(0.5))
+    );
+    assert_eq!(to_css(Length::px(10.0)), "10px");
+    assert_eq!(to_css(Percentage(0.5)), "50%");
+    assert_eq!(
+      to_css(Length::parse_string("calc(1em + 2px)").unwrap()),
+      "calc(1em + 2px)"
+    );
+    assert_eq!(
+      to_css(Length::parse_string("min(1em, 2px * 2)").unwrap()),
+      "min(1em, 4px)"
+    );
+  }
+
+  #[test]
+  fn test_from_unit() {
+    use LengthValue::*;
+    let units = [
+      ("px", Px(2.0)),
+      ("in", In(2.0)),
+      ("cm", Cm(2.0)),
+      ("mm", Mm(2.0)),
+      ("q", Q(2.0)),
+      ("pt", Pt(2.0)),
+      ("pc", Pc(2.0)),
+      ("em", Em(2.0)),
+      ("rem", Rem(2.0)),
+      ("ex", Ex(2.0)),
+      ("rex", Rex(2.0)),
+      ("ch", Ch(2.0)),
+      ("rch", Rch(2.0)),
+      ("cap", Cap(2.0)),
+      ("rcap", Rcap(2.0)),
+      ("ic", Ic(2.0)),
+      ("ric", Ric(2.0)),
+      ("lh", Lh(2.0)),
+      ("rlh", Rlh(2.0)),
+      ("vw", Vw(2.0)),
+      ("lvw", Lvw(2.0)),
+      ("svw", Svw(2.0)),
+      ("dvw", Dvw(2.0)),
+      ("cqw", Cqw(2.0)),
+      ("vh", Vh(2.0)),
+      ("lvh", Lvh(2.0)),
+      ("svh", Svh(2.0)),
+      ("dvh", Dvh(2.0)),
+      ("cqh", Cqh(2.0)),
+      ("vi", Vi(2.0)),
+      ("svi", Svi(2.0)),
+      ("lvi", Lvi(2.0)),
+      ("dvi", Dvi(2.0)),
+      ("cqi", Cqi(2.0)),
+      ("vb", Vb(2.0)),
+      ("svb", Svb(2.0)),
+      ("lvb", Lvb(2.0)),
+      ("dvb", Dvb(2.0)),
+      ("cqb", Cqb(2.0)),
+      ("vmin", Vmin(2.0)),
+      ("svmin", Svmin(2.0)),
+      ("lvmin", Lvmin(2.0)),
+      ("dvmin", Dvmin(2.0)),
+      ("cqmin", Cqmin(2.0)),
+      ("vmax", Vmax(2.0)),
+      ("svmax", Svmax(2.0)),
+      ("lvmax", Lvmax(2.0)),
+      ("dvmax", Dvmax(2.0)),
+      ("cqmax", Cqmax(2.0)),
+    ];
+    for (unit, expected) in units {
+      assert_eq!(LengthValue::from_unit(2.0, unit), Some(expected.clone()));
+      assert_eq!(
+        LengthValue::from_unit(2.0, &unit.to_uppercase()),
+        Some(expected.clone())
+      );
+      assert_eq!(expected.to_unit_value(), (2.0, unit));
+    }
+    assert_eq!(LengthValue::from_unit(2.0, "foo"), None);
+    assert_eq!(LengthValue::from_unit(2.0, ""), None);
+  }
+
+  #[test]
+  fn test_is_negative() {
+    let is_negative = |s: &str| Length::parse_string(s).unwrap().is_negative();
+    assert_eq!(is_negative("-5px"), Some(true));
+    assert_eq!(is_negative("-1em"), Some(true));
+    assert_eq!(is_negative("5px"), Some(false));
+    assert_eq!(is_negative("0px"), Some(false));
+    assert_eq!(is_negative("-0px"), Some(false));
+    assert_eq!(is_negative("calc(1px - 2px)"), Some(true));
+    assert_eq!(is_negative("calc(1px - 1em)"), None);
+    assert_eq!(is_negative("min(-1px, 1em)"), None);
+  }
+
+  #[test]
+  fn test_variant_predicates() {
+    let length = LengthPercentage::parse_string("10px").unwrap();
+    assert!(length.is_length() && !length.is_percentage() && !length.is_calc());
+    let percentage = LengthPercentage::parse_string("10%").unwrap();
+    assert!(!percentage.is_length() && percentage.is_percentage() && !percentage.is_calc());
+    let calc = LengthPercentage::parse_string("calc(10% + 1px)").unwrap();
+    assert!(!calc.is_length() && !calc.is_percentage() && calc.is_calc());
+
+    let length = Length::parse_string("10px").unwrap();
+    assert!(length.is_value() && !length.is_calc());
+    let calc = Length::parse_string("calc(1em + 1px)").unwrap();
+    assert!(!calc.is_value() && calc.is_calc());
+  }
+
+  #[test]
+  fn test_is_full_percentage() {
+    assert!(LengthPercentage::parse_string("100%").unwrap().is_full_percentage());
+    assert!(!LengthPercentage::parse_string("50%").unwrap().is_full_percentage());
+    assert!(!LengthPercentage::parse_string("100px").unwrap().is_full_percentage());
+  }
+
+  #[test]
+  fn test_dominant_term() {
+    let value = |v: LengthValue| Box::new(Calc::Value(Box::new(Length::Value(v))));
+    // calc(5px + 100px + 1em), without folding the pixel terms.
+    let sum = Calc::Sum(
+      Box::new(Calc::Sum(value(LengthValue::Px(5.0)), value(LengthValue::Px(100.0)))),
+      value(LengthValue::Em(1.0)),
+    );
+    assert_eq!(sum.dominant_term(), Some(&Length::px(100.0)));
+
+    let sum = Calc::Sum(value(LengthValue::In(1.0)), value(LengthValue::Px(-200.0)));
+    assert_eq!(sum.dominant_term(), Some(&Length::px(-200.0)));
+
+    let sum = Calc::Sum(value(LengthValue::Em(1.0)), value(LengthValue::Vw(2.0)));
+    assert_eq!(sum.dominant_term(), None);
+  }
+
+  #[test]
+  fn test_calc_expected_parenthesis() {
+    for source in ["calc[10px + 5px]", "calc {10px}", "CALC (10px)"] {
+      let err = Calc::<Length>::parse_string(source).unwrap_err();
+      assert_eq!(
+        err.kind,
+        ParseErrorKind::Custom(ParserError::MathFunctionExpectedParenthesis(source[..4].into()))
+      );
+      assert!(ParserError::MathFunctionExpectedParenthesis(source[..4].into())
+        .to_string()
+        .contains("parenthesis"));
+    }
+
+    // Other tokens are reported as unexpected.
+    assert!(matches!(
+      Calc::<Length>::parse_string("10px").unwrap_err().kind,
+      ParseErrorKind::Basic(BasicParseErrorKind::UnexpectedToken(_))
+    ));
+  }
+
+  #[test]
+  fn test_scale_units() {
+    let factors = UnitScaleMap::new().with("rem", 1.25).with("VW", 0.5);
+    let scale = |s: &str| {
+      Length::parse_string(s)
+        .unwrap()
+        .scale_units(&factors)
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(scale("calc(1rem + 10px)"), "calc(1.25rem + 10px)");
+    assert_eq!(scale("2rem"), "2.5rem");
+    assert_eq!(scale("10px"), "10px");
+    assert_eq!(scale("max(10vw, 2em)"), "max(5vw, 2em)");
+    assert_eq!(factors.get("REM"), Some(1.25));
+    assert_eq!(factors.clone().with("rem", 2.0).get("rem"), Some(2.0));
+    assert_eq!(factors.get("px"), None);
+  }
+
+  #[test]
+  fn test_percentage_accessors() {
+    assert_eq!(Percentage(0.5).as_fraction(), 0.5);
+    assert_eq!(Percentage(0.5).as_percent(), 50.0);
+    assert_eq!(Percentage::parse_string("25%").unwrap().as_percent(), 25.0);
+    assert_eq!(Percentage::parse_string("25%").unwrap().as_fraction(), 0.25);
+  }
+
+  #[test]
+  fn test_negative_percentage() {
+    let percentage = Percentage::parse_string("-50%").unwrap();
+    assert_eq!(percentage, Percentage(-0.5));
+    assert_eq!(percentage.to_css_string(PrinterOptions::default()).unwrap(), "-50%");
+    assert!(percentage.as_fraction() < 0.0);
+    assert!(percentage.as_fraction() > -1.0);
+    assert_eq!(percentage.as_percent(), -50.0);
+    assert!(percentage < Percentage(0.25));
+
+    let length = LengthPercentage::parse_string("-50%").unwrap();
+    assert_eq!(length, LengthPercentage::Percentage(Percentage(-0.5)));
+    assert_eq!(length.to_css_string(PrinterOptions::default()).unwrap(), "-50%");
+    assert!(length < LengthPercentage::Percentage(Percentage(0.0)));
+    assert_eq!(
+      LengthPercentage::parse_string("calc(-50% - 10px)")
+        .unwrap()
+        .to_css_string(PrinterOptions::default())
+        .unwrap(),
+      "calc(-50% - 10px)"
+    );
+  }
+
+  #[test]
+  fn test_ratio() {
+    assert_eq!(LengthValue::In(1.0).ratio_to_px(), Some(96.0));
+    assert_eq!(LengthValue::Em(1.0).ratio_to_px(), None);
+    assert_eq!(LengthValue::In(1.0).ratio(&LengthValue::Px(1.0)), Some(96.0));
+    assert_eq!(LengthValue::Pt(6.0).ratio(&LengthValue::Pc(1.0)), Some(0.5));
+    assert_eq!(LengthValue::Px(1.0).ratio(&LengthValue::Px(0.0)), None);
+    assert_eq!(LengthValue::Px(1.0).ratio(&LengthValue::Rem(1.0)), None);
+  }
+
+  #[test]
+  fn test_percent_diff() {
+    use LengthValue::*;
+    assert_eq!(Px(120.0).percent_diff(&Px(100.0)), Some(0.2));
+    assert_eq!(Px(50.0).percent_diff(&Px(100.0)), Some(-0.5));
+    assert_eq!(In(2.0).percent_diff(&Px(96.0)), Some(1.0));
+    assert_eq!(Px(1.0).percent_diff(&Px(0.0)), None);
+    assert_eq!(Px(1.0).percent_diff(&Em(1.0)), None);
+    assert_eq!(Vw(1.0).percent_diff(&Px(1.0)), None);
+  }
+
+  #[test]
+  fn test_try_add() {
+    assert_eq!(Length::px(1.0).try_add(Length::px(2.0)), Ok(Length::px(3.0)));
+    assert_eq!(
+      Length::Value(LengthValue::In(1.0)).try_add(Length::px(4.0)),
+      Ok(Length::px(100.0))
+    );
+    assert_eq!(
+      Length::px(1.0).try_add(Length::Value(LengthValue::Em(1.0))),
+      Err((Length::px(1.0), Length::Value(LengthValue::Em(1.0))))
+    );
+  }
+
+  #[test]
+  fn test_large_integers() {
+    let print = |s: &str| {
+      LengthValue::parse_string(s)
+        .unwrap()
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(print("16777215px"), "16777215px");
+    assert_eq!(print("16777216px"), "16777216px");
+    assert_eq!(print("-16777216px"), "-16777216px");
+    // Integers beyond MAX_SAFE_INTEGER are rounded to the nearest f32 when parsed.
+    assert_eq!(print("16777217px"), "16777216px");
+    assert_eq!(print("123456789px"), "123456792px");
+    assert_eq!(
+      LengthValue::Px(MAX_SAFE_INTEGER + 1.0),
+      LengthValue::Px(MAX_SAFE_INTEGER)
+    );
+    assert_eq!(print("1e10px"), "10000000000px");
+  }
+
+  #[test]
+  fn test_split() {
+    let split = |s: &str| LengthPercentage::parse_string(s).unwrap().split();
+    assert_eq!(split("calc(10px + 50%)"), Some((Length::px(10.0), Percentage(0.5))));
+    assert_eq!(split("calc(50% - 10px)"), Some((Length::px(-10.0), Percentage(0.5))));
+    assert_eq!(split("10px"), Some((Length::px(10.0), Percentage(0.0))));
+    assert_eq!(
+      split("2em"),
+      Some((Length::Value(LengthValue::Em(2.0)), Percentage(0.0)))
+    );
+    assert_eq!(split("50%"), Some((Length::zero(), Percentage(0.5))));
+    assert_eq!(
+      split("calc(10px + 1em + 50%)"),
+      Some((Length::parse_string("calc(10px + 1em)").unwrap(), Percentage(0.5)))
+    );
+    assert_eq!(split("min(10px, 50%)"), None);
+    assert_eq!(split("calc(50% + min(10px, 1em))"), None);
+  }
+
+  #[test]
+  fn test_decompose() {
+    let decompose = |s: &str| {
+      let (length, percentage, residual) = LengthPercentage::parse_string(s).unwrap().decompose();
+      (
+        length.to_css_string(PrinterOptions::default()).unwrap(),
+        percentage,
+        residual.map(|r| r.to_css_string(PrinterOptions::default()).unwrap()),
+      )
+    };
+    assert_eq!(
+      decompose("calc(10px + 50% + min(1em, 2vw))"),
+      ("10px".into(), Percentage(0.5), Some("min(1em, 2vw)".into()))
+    );
+    assert_eq!(
+      decompose("calc(10px + 1em + 50% - 20%)"),
+      ("calc(10px + 1em)".into(), Percentage(0.3), None)
+    );
+    assert_eq!(
+      decompose("calc(max(1px, 5%) + 10px + clamp(1px, 2vw, 3px))"),
+      (
+        "10px".into(),
+        Percentage(0.0),
+        Some("max(1px, 5%) + clamp(1px, 2vw, 3px)".into())
+      )
+    );
+    assert_eq!(decompose("10px"), ("10px".into(), Percentage(0.0), None));
+    assert_eq!(decompose("50%"), ("0".into(), Percentage(0.5), None));
+    // var() is substituted before parsing, so values containing it are not parsed as lengths.
+    assert!(LengthPercentage::parse_string("calc(10px + 50% + var(--x))").is_err());
+  }
+
+  #[test]
+  fn test_infinity() {
+    let print = |s: &str| {
+      Length::parse_string(s)
+        .unwrap()
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+
+    let length = Length::parse_string("calc(infinity * 1px)").unwrap();
+    assert_eq!(length, Length::px(f32::INFINITY));
+    assert_eq!(print("calc(infinity * 1px)"), "calc(infinity * 1px)");
+    assert_eq!(print("calc(2px * infinity)"), "calc(infinity * 1px)");
+
+    let length = Length::parse_string("calc(-infinity * 1em)").unwrap();
+    assert_eq!(length, Length::Value(LengthValue::Em(-f32::INFINITY)));
+    assert_eq!(print("calc(-infinity * 1em)"), "calc(-infinity * 1em)");
+    assert_eq!(print("calc(NaN * 1rem)"), "calc(NaN * 1rem)");
+  }
+
+  #[test]
+  fn test_parse_no_calc() {
+    assert_eq!(parse("10px", Length::parse_no_calc), Ok(Length::px(10.0)));
+    for source in [
+      "calc(1px + 2px)",
+      "min(1px, 2px)",
+      "max(1px, 2px)",
+      "clamp(1px, 2px, 3px)",
+    ] {
+      assert!(matches!(
+        parse(source, Length::parse_no_calc),
+        Err(ParseErrorKind::Custom(ParserError::MathFunctionNotAllowed(_)))
+      ));
+    }
+    assert_eq!(
+      parse("CALC(1px)", Length::parse_no_calc),
+      Err(ParseErrorKind::Custom(ParserError::MathFunctionNotAllowed(
+        "CALC".into()
+      )))
+    );
+    for source in ["var(--x)", "env(safe-area-inset-top)", "foo(1px)"] {
+      assert!(matches!(
+        parse(source, Length::parse_no_calc),
+        Err(ParseErrorKind::Basic(BasicParseErrorKind::UnexpectedToken(
+          Token::Function(_)
+        )))
+      ));
+    }
+  }
+
+  #[test]
+  fn test_needs_layout() {
+    let needs_layout = |s: &str| Length::parse_string(s).unwrap().needs_layout();
+    assert!(!needs_layout("10px"));
+    assert!(!needs_layout("1in"));
+    assert!(needs_layout("50vw"));
+    assert!(needs_layout("2em"));
+    assert!(needs_layout("10cqi"));
+    assert!(needs_layout("calc(1px + 1em)"));
+    assert!(needs_layout("min(1px, 10vh * 2)"));
+    assert!(!needs_layout("max(1px, 1in)"));
+  }
+
+  #[test]
+  fn test_parse_non_negative() {
+    let negative = || ParseErrorKind::Custom(ParserError::NegativeValue);
+    assert_eq!(parse("-5px", Length::parse_non_negative).unwrap_err(), negative());
+    assert_eq!(parse("0", Length::parse_non_negative), Ok(Length::zero()));
+    assert_eq!(parse("5px", Length::parse_non_negative), Ok(Length::px(5.0)));
+    assert!(parse("calc(1em - 5px)", Length::parse_non_negative).is_ok());
+
+    assert_eq!(
+      parse("-10%", LengthPercentage::parse_non_negative).unwrap_err(),
+      negative()
+    );
+    assert_eq!(
+      parse("-5px", LengthPercentage::parse_non_negative).unwrap_err(),
+      negative()
+    );
+    assert_eq!(
+      parse("0", LengthPercentage::parse_non_negative),
+      Ok(LengthPercentage::zero())
+    );
+    assert_eq!(
+      parse("0%", LengthPercentage::parse_non_negative),
+      Ok(LengthPercentage::Percentage(Percentage(0.0)))
+    );
+  }
+
+  #[test]
+  fn test_to_token() {
+    let token = |s: &str| Length::parse_string(s).unwrap().to_token();
+    assert_eq!(
+      token("16px"),
+      LengthToken {
+        value: 16.0,
+        unit: "px".into(),
+        resolvable_px: Some(16.0)
+      }
+    );
+    assert_eq!(
+      token("2em"),
+      LengthToken {
+        value: 2.0,
+        unit: "em".into(),
+        resolvable_px: None
+      }
+    );
+    assert_eq!(
+      token("1in"),
+      LengthToken {
+        value: 1.0,
+        unit: "in".into(),
+        resolvable_px: Some(96.0)
+      }
+    );
+    assert_eq!(
+      token("calc(1em + 2px)"),
+      LengthToken {
+        value: 0.0,
+        unit: "calc".into(),
+        resolvable_px: None
+      }
+    );
+    assert_eq!(token("min(1in, 200px)").resolvable_px, Some(96.0));
+  }
+
+  #[test]
+  fn test_rect() {
+    use crate::values::rect::Rect;
+
+    let round_trip = |s: &str| {
+      Rect::<LengthPercentage>::parse_string(s)
+        .unwrap()
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(round_trip("10px 20px 10px 20px"), "10px 20px");
+    assert_eq!(round_trip("10px 20px 30px 20px"), "10px 20px 30px");
+    assert_eq!(round_trip("10px 20px 30px 40px"), "10px 20px 30px 40px");
+    assert_eq!(round_trip("10% 10% 10% 10%"), "10%");
+    assert_eq!(round_trip("calc(10px + 5%) 0"), "calc(10px + 5%) 0");
+
+    assert_eq!(
+      Rect::<LengthPercentage>::parse_string("10px").unwrap(),
+      Rect::all(LengthPercentage::px(10.0))
+    );
+    assert_eq!(
+      Rect::<LengthPercentage>::parse_string("10px 50%").unwrap(),
+      Rect::new(
+        LengthPercentage::px(10.0),
+        LengthPercentage::Percentage(Percentage(0.5)),
+        LengthPercentage::px(10.0),
+        LengthPercentage::Percentage(Percentage(0.5))
+      )
+    );
+  }
+
+  #[test]
+  fn test_border_radius_round_trip() {
+    use crate::properties::border_radius::BorderRadius;
+
+    let round_trip = |s: &str| {
+      BorderRadius::parse_string(s)
+        .unwrap()
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(round_trip("10px"), "10px");
+    assert_eq!(round_trip("10px 20px"), "10px 20px");
+    assert_eq!(round_trip("10px / 20px"), "10px / 20px");
+    assert_eq!(round_trip("10px 20px / 10px 20px"), "10px 20px");
+    assert_eq!(round_trip("10px 5% / 20px 30px 40px"), "10px 5% / 20px 30px 40px");
+    assert!(BorderRadius::parse_string("10px /").is_err());
+    assert!(BorderRadius::parse_string("1px 2px 3px 4px 5px").is_err());
+  }
+
+  #[test]
+  fn test_single_percentage_calc() {
+    for source in ["calc(50%)", "calc(calc(50%))", "calc(25% + 25%)", "calc(100% / 2)"] {
+      let value = LengthPercentage::parse_string(source).unwrap();
+      assert_eq!(value, LengthPercentage::Percentage(Percentage(0.5)));
+      assert_eq!(value.to_css_string(PrinterOptions::default()).unwrap(), "50%");
+    }
+  }
+
+  #[test]
+  fn test_debug_tree() {
+    assert_eq!(
+      Calc::<Length>::parse_string("calc(1px + 2em)").unwrap().debug_tree(),
+      indoc::indoc! {"
+        Function(calc)
+          Sum
+            Value(Value(Px(1.0)))
+            Value(Value(Em(2.0)))
+      "}
+    );
+    assert_eq!(
+      Calc::<Length>::parse_string("min(1em, 2vw * 3, round(up, 10px, 1rem))")
+        .unwrap()
+        .debug_tree(),
+      indoc::indoc! {"
+        Function(min)
+          Value(Value(Em(1.0)))
+          Value(Value(Vw(6.0)))
+          Function(round, up)
+            Value(Value(Px(10.0)))
+            Value(Value(Rem(1.0)))
+      "}
+    );
+  }
+
+  #[test]
+  fn test_try_add_with_notes() {
+    use LengthValue::*;
+    let mut notes = Vec::new();
+    assert_eq!(In(1.0).try_add_with_notes(&Cm(2.54), &mut notes), Some(Px(192.0)));
+    assert_eq!(
+      notes,
+      vec![UnitConversionNote {
+        left: LengthUnit::In,
+        right: LengthUnit::Cm,
+        result: LengthUnit::Px,
+        lossy: true
+      }]
+    );
+
+    let mut notes = Vec::new();
+    assert_eq!(Px(4.0).try_add_with_notes(&In(1.0), &mut notes), Some(Px(100.0)));
+    assert_eq!(Em(1.0).try_add_with_notes(&Em(2.0), &mut notes), Some(Em(3.0)));
+    assert_eq!(Em(1.0).try_add_with_notes(&Px(2.0), &mut notes), None);
+    assert!(notes.is_empty());
+  }
+
+  #[test]
+  fn test_collect_terms_with_notes() {
+    use LengthValue::*;
+    let value = |v| Box::new(Calc::Value(Box::new(Length::Value(v))));
+    let mut notes = Vec::new();
+    let sum = Calc::Sum(value(In(1.0)), value(Cm(1.0)));
+    assert_eq!(
+      sum.collect_terms_with_notes(&mut notes),
+      Calc::Value(Box::new(Length::Value(In(1.0).try_add(&Cm(1.0)).unwrap())))
+    );
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0].lossy);
+
+    let mut notes = Vec::new();
+    let sum = Calc::Sum(Box::new(Calc::Sum(value(In(1.0)), value(Em(1.0)))), value(Pt(72.0)));
+    assert_eq!(
+      sum.collect_terms_with_notes(&mut notes),
+      Calc::Sum(value(Px(192.0)), value(Em(1.0)))
+    );
+    assert_eq!(
+      notes,
+      vec![UnitConversionNote {
+        left: LengthUnit::In,
+        right: LengthUnit::Pt,
+        result: LengthUnit::Px,
+        lossy: false
+      }]
+    );
+  }
+
+  #[test]
+  fn test_min_max_px() {
+    let ctx = LengthContext {
+      font_size: Some(16.0),
+      viewport_height: Some(800.0),
+      viewport_width_range: Some((320.0, 1280.0)),
+      ..LengthContext::default()
+    };
+    let bounds = |s: &str| {
+      let length = Length::parse_string(s).unwrap();
+      (length.min_px(&ctx), length.max_px(&ctx))
+    };
+    assert_eq!(bounds("50vw"), (Some(160.0), Some(640.0)));
+    assert_eq!(bounds("calc(1em - 10vw)"), (Some(-112.0), Some(-16.0)));
+    assert_eq!(bounds("10vmin"), (Some(32.0), Some(80.0)));
+    assert_eq!(bounds("10px"), (Some(10.0), Some(10.0)));
+    assert_eq!(bounds("1rem"), (None, None));
+
+    let ctx = LengthContext {
+      viewport_width_range: Some((320.0, 1280.0)),
+      viewport_height_range: Some((600.0, 900.0)),
+      ..LengthContext::default()
+    };
+    let length = Length::parse_string("calc(10vw + 10vh)").unwrap();
+    assert_eq!(length.min_px(&ctx), Some(92.0));
+    assert_eq!(length.max_px(&ctx), Some(218.0));
+  }
+
+  #[test]
+  fn test_can_fold_add() {
+    let can_fold_add = |a: &str, b: &str| {
+      let a = Length::parse_string(a).unwrap();
+      let b = Length::parse_string(b).unwrap();
+      let res = a.can_fold_add(&b);
+      assert_eq!(res, a.try_add(b).is_ok());
+      res
+    };
+    assert!(can_fold_add("1px", "2px"));
+    assert!(can_fold_add("1em", "2em"));
+    assert!(can_fold_add("1in", "2cm"));
+    assert!(!can_fold_add("1em", "2rem"));
+    assert!(!can_fold_add("1em", "2px"));
+    assert!(!can_fold_add("1vw", "2vh"));
+    assert!(!can_fold_add("min(1em, 1px)", "2px"));
+  }
+
+  #[test]
+  fn test_to_px_f64() {
+    assert_eq!(LengthValue::In(1.0).to_px_f64(), Some(96.0));
+    assert_eq!(LengthValue::Px(1.5).to_px_f64(), Some(1.5));
+    assert_eq!(LengthValue::Em(1.0).to_px_f64(), None);
+    assert_eq!(Length::Value(LengthValue::In(1.0)).to_px_f64(), Some(96.0));
+    assert_eq!(Length::parse_string("calc(1em + 1px)").unwrap().to_px_f64(), None);
+
+    // 1mm is 96 / 25.4 px, which is rounded further when computed in f32.
+    let exact = 96.0 / 25.4;
+    assert_eq!(LengthValue::Mm(1.0).to_px_f64(), Some(exact));
+    assert_ne!(LengthValue::Mm(1.0).to_px().unwrap() as f64, exact);
+  }
+
+  #[test]
+  fn test_clamp_argument_count() {
+    for source in ["clamp(1px, 10px)", "clamp(1px, 5px, 10px, 20px)", "clamp(1px)"] {
+      let err = Calc::<Length>::parse_string(source).unwrap_err();
+      assert_eq!(
+        err.kind,
+        ParseErrorKind::Custom(ParserError::MathFunctionArgumentCount("clamp".into()))
+      );
+      assert_eq!(
+        ParserError::MathFunctionArgumentCount("clamp".into()).to_string(),
+        "Wrong number of arguments passed to `clamp()`"
+      );
+    }
+
+    assert_eq!(Length::parse_string("clamp(1px, 5px, 10px)").unwrap(), Length::px(5.0));
+    assert!(Length::parse_string("clamp(1px, 1em, 10px)").is_ok());
+  }
+
+  #[test]
+  fn test_absolute_units() {
+    let units = LengthValue::absolute_units();
+    assert_eq!(units.len(), 7);
+    assert!(units.contains(&("in", 96.0)));
+    for (unit, factor) in units {
+      let value = LengthValue::from_unit(1.0, unit).unwrap();
+      assert_eq!(value.to_px(), Some(*factor));
+    }
+  }
+
+  #[test]
+  fn test_mul_distributes() {
+    let mul = |s: &str, n: CSSNumber| {
+      (LengthPercentage::parse_string(s).unwrap() * n)
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(mul("calc(10px + 50%)", 2.0), "calc(20px + 100%)");
+    assert_eq!(mul("calc(100% - 10px)", 0.5), "calc(50% - 5px)");
+    assert_eq!(mul("calc(1em + 2px + 10%)", 3.0), "calc(3em + 6px + 30%)");
+    assert_eq!(mul("min(10px, 50%)", 2.0), "2 * min(10px, 50%)");
+  }
+
+  #[test]
+  fn test_parse_with_unit() {
+    assert!(parse("0", Length::parse_with_unit).is_err());
+    assert!(parse("5", Length::parse_with_unit).is_err());
+    assert!(parse("-1.5", Length::parse_with_unit).is_err());
+    assert_eq!(parse("0px", Length::parse_with_unit), Ok(Length::px(0.0)));
+    assert_eq!(
+      parse("5em", Length::parse_with_unit),
+      Ok(Length::Value(LengthValue::Em(5.0)))
+    );
+    assert!(parse("calc(1px + 1em)", Length::parse_with_unit).is_ok());
+  }
+
+  #[test]
+  fn test_into_resolved() {
+    let resolve = |s: &str| Length::parse_string(s).unwrap().into_resolved().map(|l| l.to_px());
+    assert_eq!(resolve("10px"), Some(10.0));
+    assert_eq!(resolve("1in"), Some(96.0));
+    assert_eq!(resolve("calc(1px + 1em)"), None);
+    assert_eq!(resolve("1em"), None);
+    assert_eq!(resolve("50vw"), None);
+    assert_eq!(Length::from(Length::px(4.0).into_resolved().unwrap()), Length::px(4.0));
+  }
+
+  #[test]
+  fn test_parse_all() {
+    assert_eq!(
+      parse("10px 20px 30px", Length::parse_all),
+      Ok(vec![Length::px(10.0), Length::px(20.0), Length::px(30.0)])
+    );
+    assert_eq!(
+      parse("1em, calc(1px + 1em),2px", Length::parse_all),
+      Ok(vec![
+        Length::Value(LengthValue::Em(1.0)),
+        Length::parse_string("calc(1px + 1em)").unwrap(),
+        Length::px(2.0)
+      ])
+    );
+    assert!(parse("", Length::parse_all).is_err());
+    assert!(parse("10px red", Length::parse_all).is_err());
+  }
+
+  #[test]
+  fn test_uses_viewport_units() {
+    let uses_viewport_units = |s: &str| Length::parse_string(s).unwrap().uses_viewport_units();
+    assert!(uses_viewport_units("50vw"));
+    assert!(uses_viewport_units("10dvh"));
+    assert!(uses_viewport_units("1svmin"));
+    assert!(uses_viewport_units("calc(1px + 2lvb)"));
+    assert!(uses_viewport_units("max(1px, 2em * 3, 1vmax)"));
+    assert!(!uses_viewport_units("calc(1px + 2em)"));
+    assert!(!uses_viewport_units("10cqw"));
+    assert!(!uses_viewport_units("1in"));
+  }
+
+  #[test]
+  fn test_em_to_rem() {
+    let em_to_rem = |s: &str| {
+      Length::parse_string(s)
+        .unwrap()
+        .em_to_rem(32.0, 16.0)
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(em_to_rem("2em"), "4rem");
+    assert_eq!(em_to_rem("2rem"), "2rem");
+    assert_eq!(em_to_rem("10px"), "10px");
+    assert_eq!(em_to_rem("calc(1em + 10px)"), "calc(2rem + 10px)");
+    assert_eq!(em_to_rem("max(1em, 1ex)"), "max(2rem, 1ex)");
+  }
+
+  #[test]
+  fn test_length_stats() {
+    let mut stats = LengthStats::default();
+    for s in [
+      "10px",
+      "1in",
+      "-2pt",
+      "1em",
+      "2rem",
+      "50vw",
+      "1dvh",
+      "3cqi",
+      "calc(1px + 1em)",
+    ] {
+      stats.record(&Length::parse_string(s).unwrap());
+    }
+    assert_eq!(
+      stats,
+      LengthStats {
+        absolute: 3,
+        font_relative: 2,
+        viewport_relative: 2,
+        container_relative: 1,
+        calc: 1,
+        min_px: Some(-2.0 * 96.0 / 72.0),
+        max_px: Some(96.0),
+      }
+    );
+    assert_eq!(LengthValue::Svmin(1.0).unit_kind(), LengthUnitKind::ViewportRelative);
+    assert_eq!(LengthValue::Rlh(1.0).unit_kind(), LengthUnitKind::FontRelative);
+  }
+
+  #[test]
+  fn test_auto_case_insensitive() {
+    for s in ["auto", "AUTO", "Auto"] {
+      let value = LengthPercentageOrAuto::parse_string(s).unwrap();
+      assert_eq!(value, LengthPercentageOrAuto::Auto);
+      assert_eq!(value.to_css_string(PrinterOptions::default()).unwrap(), "auto");
+    }
+    assert!(LengthPercentageOrAuto::parse_string("autox").is_err());
+  }
+
+  #[test]
+  fn test_calc_spacing() {
+    let print = |s: &str, minify: bool| {
+      Length::parse_string(s)
+        .unwrap()
+        .to_css_string(PrinterOptions {
+          minify,
+          ..PrinterOptions::default()
+        })
+        .unwrap()
+    };
+    assert_eq!(
+      print("calc(  100vw -  ( 2 * ( 1em  +  1vh ) ) )", false),
+      "calc(100vw - 2em - 2vh)"
+    );
+    assert_eq!(
+      print("calc(1em + max( 1vw , 2vh ) * 2)", false),
+      "calc(1em + 2 * max(1vw, 2vh))"
+    );
+    assert_eq!(
+      print("calc(1em + max( 1vw , 2vh ) * 2)", true),
+      "calc(1em + 2*max(1vw,2vh))"
+    );
+    assert_eq!(print("min(calc(1em - 1vw), 10px)", false), "min(1em - 1vw, 10px)");
+    assert_eq!(print("calc(10vw - 5em - 3vh)", false), "calc(10vw - 5em - 3vh)");
+    assert_eq!(print("calc(-5em - 2vw + 10px)", false), "calc(10px - 5em - 2vw)");
+    assert_eq!(print("calc(-5em - 2vw - 10px)", false), "calc(-5em - 2vw - 10px)");
+    assert_eq!(print("calc(10vw - (5em + 3vh))", false), "calc(10vw - 5em - 3vh)");
+  }
+
+  #[test]
+  fn test_parse_with_location() {
+    assert_eq!(
+      parse("  10px", Length::parse_with_location),
+      Ok((Length::px(10.0), SourceLocation { line: 0, column: 3 }))
+    );
+    assert_eq!(
+      parse("1px\n  calc(1em + 2px)", |input| {
+        Length::parse(input)?;
+        Length::parse_with_location(input)
+      }),
+      Ok((
+        Length::parse_string("calc(1em + 2px)").unwrap(),
+        SourceLocation { line: 1, column: 3 }
+      ))
+    );
+  }
+
+  #[test]
+  fn test_sort_key() {
+    let ctx = LengthContext {
+      font_size: Some(16.0),
+      ..LengthContext::default()
+    };
+    let mut stops: Vec<LengthPercentage> = ["50%", "1vw", "10px", "calc(25% + 1em)", "2cqw", "2em"]
+      .iter()
+      .map(|s| LengthPercentage::parse_string(s).unwrap())
+      .collect();
+    stops.sort_by(|a, b| match (a.sort_key(&ctx, 200.0), b.sort_key(&ctx, 200.0)) {
+      (Some(a), Some(b)) => a.partial_cmp(&b).unwrap(),
+      (Some(_), None) => std::cmp::Ordering::Less,
+      (None, Some(_)) => std::cmp::Ordering::Greater,
+      (None, None) => std::cmp::Ordering::Equal,
+    });
+    let stops: Vec<String> = stops
+      .iter()
+      .map(|s| s.to_css_string(PrinterOptions::default()).unwrap())
+      .collect();
+    assert_eq!(stops, ["10px", "2em", "calc(1em + 25%)", "50%", "1vw", "2cqw"]);
+  }
+
+  #[test]
+  fn test_is_percentage_calc() {
+    let percentage = |p: CSSNumber| Box::new(Calc::Value(Box::new(LengthPercentage::Percentage(Percentage(p)))));
+    let sum = LengthPercentage::Calc(Box::new(Calc::Function(Box::new(MathFunction::Calc(Calc::Sum(
+      percentage(0.5),
+      Box::new(Calc::Product(2.0, percentage(0.25))),
+    ))))));
+    assert!(sum.is_percentage_calc());
+
+    let mixed = LengthPercentage::parse_string("calc(10px + 50%)").unwrap();
+    assert!(!mixed.is_percentage_calc());
+    assert!(!LengthPercentage::parse_string("max(1em, 50%)").unwrap().is_percentage_calc());
+    assert!(!LengthPercentage::parse_string("50%").unwrap().is_percentage_calc());
+    assert!(!LengthPercentage::parse_string("10px").unwrap().is_percentage_calc());
+  }
+
+  #[test]
+  fn test_subtract_from_zero() {
+    let print = |s: &str| {
+      Length::parse_string(s)
+        .unwrap()
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(print("calc(0px - 5px)"), "-5px");
+    assert_eq!(print("calc(0rem - 5vw)"), "-5vw");
+    assert_eq!(print("calc(0px - (5px + 1em))"), "calc(-5px - 1em)");
+    assert_eq!(
+      Length::px(0.0) + Length::Value(LengthValue::Em(-5.0)),
+      Length::Value(LengthValue::Em(-5.0))
+    );
+  }
+
+  #[test]
+  fn test_length_interner() {
+    use std::rc::Rc;
+    let mut interner = LengthInterner::new();
+    let a = interner.intern(Length::px(10.0));
+    let b = interner.intern(Length::parse_string("10px").unwrap());
+    assert!(Rc::ptr_eq(&a, &b));
+    assert_eq!(*a, Length::px(10.0));
+
+    let c = interner.intern(Length::Value(LengthValue::Em(10.0)));
+    assert!(!Rc::ptr_eq(&a, &c));
+    assert!(Rc::ptr_eq(
+      &interner.intern(Length::px(0.0)),
+      &interner.intern(Length::px(-0.0))
+    ));
+
+    let d = interner.intern(Length::parse_string("calc(1em + 1px)").unwrap());
+    let e = interner.intern(Length::parse_string("calc(1em + 1px)").unwrap());
+    assert!(Rc::ptr_eq(&d, &e));
+    assert_eq!(interner.len(), 4);
+
+    // Expressions that serialize the same, but are not equal, are kept separate.
+    let f = interner.intern(Length::parse_string("calc(1.0000001px + 1em)").unwrap());
+    let g = interner.intern(Length::parse_string("calc(1px + 1em)").unwrap());
+    assert!(!Rc::ptr_eq(&f, &g));
+    assert_eq!(*g, Length::parse_string("calc(1px + 1em)").unwrap());
+    assert_eq!(interner.len(), 6);
+  }
+
+  #[test]
+  fn test_unknown_units() {
+    let unknown = LengthOrUnknown::parse_string("10foo").unwrap();
+    assert_eq!(
+      unknown,
+      LengthOrUnknown::Unknown {
+        value: 10.0,
+        unit: "foo".into()
+      }
+    );
+    assert_eq!(unknown.to_css_string(PrinterOptions::default()).unwrap(), "10foo");
+    assert_eq!(
+      LengthOrUnknown::parse_string("10px").unwrap(),
+      LengthOrUnknown::Length {
+        value: Length::px(10.0)
+      }
+    );
+    assert!(LengthOrUnknown::parse_string("10").is_ok());
+    assert!(LengthOrUnknown::parse_string("foo").is_err());
+    assert!(LengthOrUnknown::parse_string("1e39foo").is_err());
+    assert!(Length::parse_string("10foo").is_err());
+  }
+
+  #[test]
+  fn test_serialized_len() {
+    assert_eq!(Length::px(100.0).serialized_len(), 5);
+    assert_eq!(Length::zero().serialized_len(), 1);
+    let calc = Length::parse_string("calc(1px + 2em)").unwrap();
+    assert_eq!(
+      calc.serialized_len(),
+      calc.to_css_string(PrinterOptions::default()).unwrap().len()
+    );
+  }
+
+  #[test]
+  fn test_bake_percentage() {
+    let bake = |source: &str, hundred: Length| {
+      LengthPercentage::parse_string(source)
+        .unwrap()
+        .bake_percentage(hundred)
+        .to_css_string(PrinterOptions::default())
+        .unwrap()
+    };
+    assert_eq!(bake("calc(50% + 10px)", Length::px(200.0)), "110px");
+    assert_eq!(bake("25%", Length::px(200.0)), "50px");
+    assert_eq!(bake("10px", Length::px(200.0)), "10px");
+    assert_eq!(bake("calc(50% + 1em)", Length::px(200.0)), "calc(1em + 100px)");
+    assert_eq!(bake("min(50%, 10px)", Length::px(200.0)), "min(100px, 10px)");
+  }
+
+  #[test]
+  fn test_round_trip_all_units() {
+    for value in LengthValue::all_units(1.5) {
+      let css = value.to_css_string(PrinterOptions::default()).unwrap();
+      assert_eq!(LengthValue::parse_string(&css), Ok(value.clone()), "{}", css);
+      assert_eq!(Length::parse_string(&css), Ok(Length::Value(value)), "{}", css);
+    }
+  }
+
+  #[test]
+  fn test_round() {
+    assert_eq!(Length::parse_string("10.6px").unwrap().round(), Length::px(11.0));
+    assert_eq!(
+      Length::parse_string("1.4in").unwrap().round(),
+      Length::Value(LengthValue::In(1.0))
+    );
+    assert_eq!(
+      Length::parse_string("1.4em").unwrap().round(),
+      Length::Value(LengthValue::Em(1.4))
+    );
+    let calc = Length::parse_string("calc(1.5px + 1em)").unwrap();
+    assert_eq!(calc.round(), calc);
+  }
+
+  #[test]
+  fn test_percentage_whitespace() {
+    // `50 %` is tokenized as a number followed by a `%` delimiter, not as a percentage.
+    assert!(parse("50 %", Percentage::parse).is_err());
+    assert!(Percentage::parse_string("50 %").is_err());
+    assert!(LengthPercentage::parse_string("50 %").is_err());
+    assert!(LengthPercentage::parse_string("calc(50 % + 10px)").is_err());
+    assert_eq!(Percentage::parse_string("50%").unwrap(), Percentage(0.5));
+  }
+
+  #[test]
+  fn test_sort_by_px() {
+    let mut lengths: Vec<ByPx> = ["1in", "1em", "10px", "50pt"]
+      .iter()
+      .map(|s| ByPx(Length::parse_string(s).unwrap()))
+      .collect();
+    lengths.sort();
+    let sorted: Vec<String> = lengths
+      .iter()
+      .map(|l| l.0.to_css_string(PrinterOptions::default()).unwrap())
+      .collect();
+    assert_eq!(sorted, ["10px", "50pt", "1in", "1em"]);
+    assert_eq!(ByPx(Length::parse_string("1in").unwrap()), ByPx(Length::px(96.0)));
+  }
+
+  #[test]
+  fn test_length_percentage_or_auto_calc() {
+    assert_eq!(
+      LengthPercentageOrAuto::parse_string("auto").unwrap(),
+      LengthPercentageOrAuto::Auto
+    );
+    assert_eq!(
+      LengthPercentageOrAuto::parse_string("calc(10px)").unwrap(),
+      LengthPercentageOrAuto::LengthPercentage(LengthPercentage::px(10.0))
+    );
+    // `auto` is a keyword, not a value that can be used within calc().
+    assert!(LengthPercentageOrAuto::parse_string("calc(auto)").is_err());
+    assert!(LengthPercentageOrAuto::parse_string("calc(auto + 10px)").is_err());
+  }
+
+  #[test]
+  fn test_calc_is_boxed() {
+    // calc() expressions are boxed so that they don't inflate the size of plain values.
+    use std::mem::size_of;
+    assert!(size_of::<Length>() <= 16);
+    assert!(size_of::<Length>() < size_of::<Calc<Length>>());
+    assert!(size_of::<LengthPercentage>() <= 16);
+    assert!(size_of::<LengthPercentage>() < size_of::<Calc<LengthPercentage>>());
+  }
+
+  #[test]
+  fn test_new() {
+    let length = Length::new(2.0, LengthUnit::Rem);
+    assert_eq!(length, Length::Value(LengthValue::Rem(2.0)));
+    assert_eq!(length.to_css_string(PrinterOptions::default()).unwrap(), "2rem");
+    assert_eq!(Length::new(10.0, LengthUnit::Px), Length::px(10.0));
+    for value in LengthValue::all_units(1.0) {
+      assert_eq!(LengthValue::new(1.0, value.unit()), value);
+    }
+  }
+
+  #[test]
+  fn test_snap_to_scale() {
+    assert_eq!(Length::px(15.0).snap_to_scale(16.0, 1.25), Length::px(16.0));
+    assert_eq!(Length::px(19.0).snap_to_scale(16.0, 1.25), Length::px(20.0));
+    assert_eq!(Length::px(13.0).snap_to_scale(16.0, 1.25), Length::px(12.8));
+    assert_eq!(
+      Length::parse_string("0.25in").unwrap().snap_to_scale(16.0, 1.25),
+      Length::px(25.0)
+    );
+    let em = Length::parse_string("1.1em").unwrap();
+    assert_eq!(em.snap_to_scale(16.0, 1.25), em);
+    assert_eq!(Length::px(0.0).snap_to_scale(16.0, 1.25), Length::px(0.0));
+  }
+
+  #[test]
+  fn test_non_ascii_digits() {
+    assert!(Length::parse_string("１０px").is_err());
+    assert!(Length::parse_string("１0px").is_err());
+    assert!(Length::parse_string("calc(１０px + 1px)").is_err());
+    assert!(LengthPercentage::parse_string("５０%").is_err());
+    assert!(Length::parse_string("٣px").is_err());
+  }
+
+  #[test]
+  fn test_logical_length() {
+    let logical = LogicalLength::new(Length::px(10.0), Length::px(20.0));
+    assert_eq!(
+      logical.resolve(WritingMode::HorizontalTb),
+      (Length::px(20.0), Length::px(10.0))
+    );
+    assert_eq!(
+      logical.resolve(WritingMode::VerticalRl),
+      (Length::px(10.0), Length::px(20.0))
+    );
+    assert_eq!(
+      logical.resolve(WritingMode::SidewaysLr),
+      logical.resolve(WritingMode::VerticalLr)
+    );
+    assert_eq!(
+      WritingMode::parse_string("vertical-rl").unwrap(),
+      WritingMode::VerticalRl
+    );
+  }
+
+  #[test]
+  fn test_serialize_dimension_scratch() {
+    let mut s = String::new();
+    let mut printer = Printer::new(&mut s, PrinterOptions::default());
+    for length in [0.5, -0.25, 10.0, 0.125, -3.5] {
+      LengthValue::Em(length).to_css(&mut printer).unwrap();
+      printer.write_char(' ').unwrap();
+    }
+    assert_eq!(s, ".5em -.25em 10em .125em -3.5em ");
+  }
+}
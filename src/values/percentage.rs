@@ -15,6 +15,10 @@ use cssparser::*;
 ///
 /// Percentages may be explicit or computed by `calc()`, but are always stored and serialized
 /// as their computed value.
+///
+/// The inner value is stored as a fraction, so `50%` is `Percentage(0.5)`, and comparisons
+/// against raw numbers use the fraction as well. Use [as_fraction](Percentage::as_fraction) or
+/// [as_percent](Percentage::as_percent) to make such comparisons explicit.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "visitor", derive(Visit))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
@@ -22,6 +26,18 @@ use cssparser::*;
 #[cfg_attr(feature = "into_owned", derive(static_self::IntoOwned))]
 pub struct Percentage(pub CSSNumber);
 
+impl Percentage {
+  /// Returns the percentage as a fraction, e.g. `0.5` for `50%`.
+  pub fn as_fraction(&self) -> CSSNumber {
+    self.0
+  }
+
+  /// Returns the percentage in percent, e.g. `50.0` for `50%`.
+  pub fn as_percent(&self) -> CSSNumber {
+    self.0 * 100.0
+  }
+}
+
 impl<'i> Parse<'i> for Percentage {
   fn parse<'t>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i, ParserError<'i>>> {
     match input.try_parse(Calc::parse) {
@@ -369,7 +385,13 @@ impl<D: TryAdd<D> + Clone + Zero + TrySign + std::fmt::Debug> DimensionPercentag
       return a;
     }
 
-    if a.is_sign_negative() && b.is_sign_positive() {
+    // Lead with a positive term if possible, and otherwise put dimensions before percentages,
+    // e.g. `calc(10px + 50%)` and `calc(100% - 10px)`.
+    if (a.is_sign_negative() && b.is_sign_positive())
+      || (matches!(a, DimensionPercentage::Percentage(_))
+        && matches!(b, DimensionPercentage::Dimension(_))
+        && b.is_sign_positive())
+    {
       std::mem::swap(&mut a, &mut b);
     }
 
@@ -396,6 +418,14 @@ impl<D: TryAdd<D> + Clone + Zero + TrySign + std::fmt::Debug> DimensionPercentag
   }
 }
 
+impl<D: TryAdd<D> + Clone + Zero + TrySign + std::fmt::Debug> TryAdd<DimensionPercentage<D>>
+  for DimensionPercentage<D>
+{
+  fn try_add(&self, other: &DimensionPercentage<D>) -> Option<DimensionPercentage<D>> {
+    self.add_recursive(other)
+  }
+}
+
 impl<D> std::convert::Into<Calc<DimensionPercentage<D>>> for DimensionPercentage<D> {
   fn into(self) -> Calc<DimensionPercentage<D>> {
     match self {
@@ -411,6 +441,12 @@ impl<D> std::convert::From<Calc<DimensionPercentage<D>>> for DimensionPercentage
   }
 }
 
+impl<D> std::convert::From<Percentage> for DimensionPercentage<D> {
+  fn from(percentage: Percentage) -> DimensionPercentage<D> {
+    DimensionPercentage::Percentage(percentage)
+  }
+}
+
 impl<D: std::cmp::PartialOrd<D>> std::cmp::PartialOrd<DimensionPercentage<D>> for DimensionPercentage<D> {
   fn partial_cmp(&self, other: &DimensionPercentage<D>) -> Option<std::cmp::Ordering> {
     match (self, other) {